@@ -0,0 +1,79 @@
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use chumsky::prelude::Parser;
+use rust_ifj22_compiler::lexer;
+use rust_ifj22_compiler::lexer::fast_lexer;
+use rust_ifj22_compiler::source_map::{SourceFile, SourceMap};
+
+const REPETITIONS: usize = 2000;
+const RUNS: u32 = 10;
+
+/// Builds a large program out of the valid test files
+fn stress_input() -> String {
+    let mut src = String::from("<?php\ndeclare(strict_types=1);\n");
+
+    for file in [
+        "identifiers/names.txt",
+        "numbers/literals.txt",
+        "strings/escapes.txt",
+        "strings/interpolation.txt",
+        "strings/heredoc.txt",
+        "lossless/trivia.txt",
+    ] {
+        let code = fs::read_to_string(format!("tests/common/{file}")).unwrap();
+
+        // Skip the prologue and the epilogue, only the first file gets to keep them
+        let code = code
+            .split_once("declare(strict_types=1);")
+            .map_or(code.as_str(), |(_, code)| code)
+            .replace("?>", "");
+
+        for _ in 0..REPETITIONS / 6 {
+            src.push_str(&code);
+            src.push('\n');
+        }
+    }
+
+    src
+}
+
+fn measure(name: &str, src: &SourceFile, lex: impl Fn(&SourceFile) -> usize) -> Duration {
+    // Warm up
+    black_box(lex(src));
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(lex(src));
+    }
+    let elapsed = start.elapsed() / RUNS;
+
+    let throughput = src.src().len() as f64 / elapsed.as_secs_f64() / 1024.0 / 1024.0;
+    println!("{name:>12}: {elapsed:>12.3?} per run, {throughput:>8.2} MiB/s");
+
+    elapsed
+}
+
+fn main() {
+    let mut map = SourceMap::new();
+    let id = map.add("stress", stress_input());
+    let src = &map[id];
+    println!("Lexing {} KiB", src.src().len() / 1024);
+
+    let combinator = measure("combinator", src, |src| {
+        lexer(src)
+            .parse_recovery(src.stream())
+            .0
+            .map_or(0, |(tokens, _)| tokens.len())
+    });
+
+    let fast = measure("fast", src, |src| {
+        fast_lexer(src).0.map_or(0, |(tokens, _)| tokens.len())
+    });
+
+    println!(
+        "The fast lexer is {:.1}x faster",
+        combinator.as_secs_f64() / fast.as_secs_f64()
+    );
+}
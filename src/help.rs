@@ -1,7 +1,10 @@
+use std::fmt::{Debug, Formatter};
 use std::ops::{Deref, DerefMut, Range};
 
 use ariadne::{Color, ReportKind};
 
+use crate::source_map::FileId;
+
 pub trait ToColor {
     fn color(&self) -> Color;
 }
@@ -17,7 +20,65 @@ impl ToColor for ReportKind {
     }
 }
 
-pub type Span = Range<usize>;
+/// A byte range in one of the files of a [`SourceMap`](crate::source_map::SourceMap)
+#[derive(Default, Clone, Hash, Eq, PartialEq)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, range: Range<usize>) -> Self {
+        Self {
+            file,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// An empty span right after this one
+    pub fn after(&self) -> Self {
+        Self::new(self.file, self.end..self.end)
+    }
+
+    /// A span from the start of this one to the end of the other one
+    pub fn join(&self, other: &Span) -> Self {
+        Self::new(self.file, self.start..other.end)
+    }
+}
+
+// `Span { .. }` makes token dumps unreadable
+impl Debug for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl chumsky::Span for Span {
+    type Context = FileId;
+    type Offset = usize;
+
+    fn new(file: FileId, range: Range<usize>) -> Self {
+        Span::new(file, range)
+    }
+
+    fn context(&self) -> FileId {
+        self.file
+    }
+
+    fn start(&self) -> usize {
+        self.start
+    }
+
+    fn end(&self) -> usize {
+        self.end
+    }
+}
 
 #[derive(Debug, Default, Clone, Hash, Eq, PartialEq)]
 pub struct WithSpan<T>(pub Span, pub T);
@@ -1,149 +1,437 @@
 mod error;
+mod fast;
+mod number;
+mod string;
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::rc::Rc;
 
 use chumsky::prelude::*;
-pub use error::print_lexer_errors;
+pub use error::print_errors;
+pub use fast::fast_lexer;
+use number::number;
+use string::{heredoc, string};
 
-use crate::token::Token;
-use crate::{error, unreachable, Span};
+use crate::source_map::SourceFile;
+use crate::syntactic_analysis::ast::Type;
+use crate::token::{Lexeme, Literals, Operator, Token, Trivia};
+use crate::{error, warn, Span, Symbol};
 
-fn number() -> impl Parser<char, Token, Error = Simple<char>> {
-    let exp = just('e')
-        .or(just('E'))
-        .chain(just('+').or(just('-')).or_not())
-        .chain::<char, _, _>(text::digits(10));
+const OPERATORS: [Operator; 15] = [
+    Operator::Assign,
+    Operator::Equals,
+    Operator::NotEquals,
+    Operator::Less,
+    Operator::LessEq,
+    Operator::Greater,
+    Operator::GreaterEq,
+    Operator::Add,
+    Operator::Subtract,
+    Operator::Multiply,
+    Operator::Divide,
+    Operator::Concat,
+    Operator::Not,
+    Operator::And,
+    Operator::Or,
+];
 
-    let frac = just('.').chain(text::digits(10));
+/// PHP operators that aren't a part of IFJ22.
+///
+/// They're lexed only so they can be reported, the lexer recovers by emitting the operator next to them.
+const UNSUPPORTED_OPERATORS: [(&str, Operator, &str); 11] = [
+    (
+        "==",
+        Operator::Equals,
+        "loose comparison isn't supported, use `===` instead",
+    ),
+    (
+        "!=",
+        Operator::NotEquals,
+        "loose comparison isn't supported, use `!==` instead",
+    ),
+    (
+        "<>",
+        Operator::NotEquals,
+        "loose comparison isn't supported, use `!==` instead",
+    ),
+    (
+        "**",
+        Operator::Multiply,
+        "the exponentiation operator isn't supported",
+    ),
+    (
+        "++",
+        Operator::Add,
+        "the increment operator isn't supported",
+    ),
+    (
+        "--",
+        Operator::Subtract,
+        "the decrement operator isn't supported",
+    ),
+    (
+        "+=",
+        Operator::Assign,
+        "compound assignment isn't supported",
+    ),
+    (
+        "-=",
+        Operator::Assign,
+        "compound assignment isn't supported",
+    ),
+    (
+        "*=",
+        Operator::Assign,
+        "compound assignment isn't supported",
+    ),
+    (
+        "/=",
+        Operator::Assign,
+        "compound assignment isn't supported",
+    ),
+    (
+        ".=",
+        Operator::Assign,
+        "compound assignment isn't supported",
+    ),
+];
 
-    text::int(10)
-        .chain::<char, _, _>(frac.or_not().flatten())
-        .chain::<char, _, _>(exp.or_not().flatten())
-        .collect::<String>()
-        .labelled("number")
-        .map(Token::Num)
+/// Parses operators using maximal munch, `$a=-1` is lexed as `=` followed by `-`
+fn operator() -> impl Parser<char, Token, Error = Simple<char, Span>> {
+    let mut table = OPERATORS
+        .iter()
+        .map(|op| (op.as_str(), *op, None))
+        .chain(
+            UNSUPPORTED_OPERATORS
+                .iter()
+                .map(|(seq, op, msg)| (*seq, *op, Some(*msg))),
+        )
+        .collect::<Vec<_>>();
+
+    // The longest operators have to be tried first
+    table.sort_by_key(|(seq, ..)| Reverse(seq.len()));
+
+    table
+        .into_iter()
+        .map(|(seq, op, msg)| just(seq).to((op, msg)).boxed())
+        .reduce(|longer, shorter| longer.or(shorter).boxed())
+        .expect("the operator table is empty")
+        // Errors can't be emitted inside the alternatives, `or` would prefer the shorter operator
+        .validate(|(op, msg), span, emit| {
+            if let Some(msg) = msg {
+                emit(error!(span, msg));
+            }
+
+            Token::Op(op)
+        })
+        .labelled("operator")
 }
 
-pub fn lexer() -> impl Parser<char, Vec<(Token, Span)>, Error = Simple<char>> {
-    let num = number();
+/// Parses an identifier following PHP's grammar, non ASCII characters are allowed like in PHP.
+///
+/// The identifier is sliced out of the source instead of being collected.
+fn php_ident(src: &str) -> impl Parser<char, &str, Error = Simple<char, Span>> + Clone + '_ {
+    filter(|c: &char| c.is_ascii_alphabetic() || *c == '_' || !c.is_ascii())
+        .ignored()
+        .then_ignore(
+            filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_' || !c.is_ascii())
+                .ignored()
+                .repeated(),
+        )
+        .map_with_span(move |_, span: Span| &src[span.range()])
+}
 
-    let operator = one_of("+-*/.=!<>")
-        .repeated()
-        .at_least(1)
-        .collect::<String>()
-        .map(Token::Op);
-
-    let escape = just('\\').ignore_then(
-        just('\\')
-            .or(just('"').to('\"'))
-            .or(just('n').to('\n'))
-            .or(just('r').to('\r'))
-            .or(just('t').to('\t'))
-            .or(just('v').to('\x0B')) // vertical tab
-            .or(just('e').to('\x1B')) // escape
-            .or(just('f').to('\x0C'))
-            .or(
-                filter(|c: &char| c.is_ascii_digit() && *c != '9' && *c != '8')
-                    .repeated()
-                    .exactly(3)
-                    .collect::<String>()
-                    .validate(|digits, span: Span, emit| {
-                        if let Ok(parsed_octal) = u32::from_str_radix(&digits, 8) {
-                            if parsed_octal > 0xFF {
-                                emit(error!(span, "octal can't be more than 377"));
-                                return '\u{FFFD}'; // unicode replacement character
-                            }
-
-                            char::from_u32(parsed_octal).unwrap_or_else(|| {
-                                emit(error!(span, "invalid ascii character"));
-                                '\u{FFFD}' // unicode replacement character
-                            })
-                        } else {
-                            emit(unreachable!(span));
-                            '\u{FFFD}' // unicode replacement character
-                        }
-                    }),
-            ) // form feed
-            .or(just('x').ignore_then(
-                filter(|c: &char| c.is_ascii_hexdigit())
-                    .repeated()
-                    .exactly(2)
-                    .collect::<String>()
-                    .validate(|digits, span: Span, emit| {
-                        if let Ok(parsed_hex) = u32::from_str_radix(&digits, 16) {
-                            char::from_u32(parsed_hex).unwrap_or_else(|| {
-                                emit(error!(span, "invalid ascii character")); // Should be unreachable
-                                '\u{FFFD}' // unicode replacement character
-                            })
-                        } else {
-                            emit(unreachable!(span));
-                            '\u{FFFD}' // unicode replacement character
-                        }
-                    }),
-            ))
-            .or(just('u').ignore_then(
-                filter(|c: &char| c.is_ascii_hexdigit())
-                    .repeated()
-                    .exactly(4)
-                    .collect::<String>()
-                    .validate(|digits, span, emit| {
-                        char::from_u32(u32::from_str_radix(&digits, 16).unwrap()).unwrap_or_else(
-                            || {
-                                emit(Simple::custom(span, "invalid unicode character"));
-                                '\u{FFFD}' // unicode replacement character
-                            },
-                        )
-                    }),
-            )),
-    );
-
-    // A parser for strings
-    let string = just('"')
+/// Lowercases a name on the stack, names longer than any keyword are skipped
+fn keyword_case<T>(name: &str, f: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let mut buf = [0; "function".len()];
+    let lowercase = buf.get_mut(..name.len())?;
+    lowercase.copy_from_slice(name.as_bytes());
+    lowercase.make_ascii_lowercase();
+
+    f(std::str::from_utf8(lowercase).ok()?)
+}
+
+/// Type names are case insensitive like in PHP
+fn type_name(name: &str) -> Option<Type> {
+    keyword_case(name, |name| match name {
+        "int" => Some(Type::Int { nullable: false }),
+        "float" => Some(Type::Float { nullable: false }),
+        "string" => Some(Type::String { nullable: false }),
+        "void" => Some(Type::Void),
+        _ => None,
+    })
+}
+
+/// Keywords, the `and` and `or` operators and the `true`, `false` and `null` literals are case insensitive like in PHP
+fn keyword(name: &str) -> Option<Token> {
+    keyword_case(name, |name| match name {
+        "function" => Some(Token::Function),
+        "if" => Some(Token::If),
+        "else" => Some(Token::Else),
+        "elseif" => Some(Token::ElseIf),
+        "true" => Some(Token::Bool(true)),
+        "false" => Some(Token::Bool(false)),
+        "null" => Some(Token::Null),
+        "while" => Some(Token::While),
+        "for" => Some(Token::For),
+        "break" => Some(Token::Break),
+        "continue" => Some(Token::Continue),
+        "return" => Some(Token::Return),
+        "and" => Some(Token::Op(Operator::AndKeyword)),
+        "or" => Some(Token::Op(Operator::OrKeyword)),
+        _ => type_name(name).map(Token::Type),
+    })
+}
+
+/// Parses a `$` prefixed variable name, the name is stored without the `$`
+fn variable(src: &str) -> impl Parser<char, Token, Error = Simple<char, Span>> + Clone + '_ {
+    just('$')
+        .ignore_then(php_ident(src).or_not())
+        .validate(|name, span, emit| {
+            Symbol::intern(name.unwrap_or_else(|| {
+                emit(error!(span, "`$` must be followed by a name"));
+                ""
+            }))
+        })
+        .labelled("variable")
+        .map(Token::Var)
+}
+
+/// Parses a `?` prefixed type like `?int`
+fn nullable_type(src: &str) -> impl Parser<char, Token, Error = Simple<char, Span>> + '_ {
+    just('?')
+        .ignore_then(php_ident(src).try_map(|name, span| {
+            type_name(name).ok_or_else(|| error!(span, format!("`{name}` is not a type")))
+        }))
+        .validate(|r#type, span, emit| {
+            r#type.to_nullable().unwrap_or_else(|| {
+                emit(error!(span, "a void return value is non nullable"));
+                r#type
+            })
+        })
+        .labelled("nullable type")
+        .map(Token::Type)
+}
+
+fn comment() -> impl Parser<char, Trivia, Error = Simple<char, Span>> + Clone {
+    let line = just("//")
+        .then(filter(|c: &char| *c != '\n').repeated())
+        .to(Trivia::LineComment);
+
+    let hash = just('#')
+        .then(filter(|c: &char| *c != '\n').repeated())
+        .to(Trivia::HashComment);
+
+    // An unterminated block comment is closed at the end of the file
+    let block = just("/*")
+        .ignore_then(take_until(just("*/").to(true).or(end().to(false))))
+        .validate(|(_, closed), span, emit| {
+            if !closed {
+                emit(error!(span, "unterminated block comment"));
+            }
+
+            Trivia::BlockComment
+        });
+
+    line.or(hash).or(block)
+}
+
+/// Skips any whitespace and comments
+fn skip_trivia() -> impl Parser<char, (), Error = Simple<char, Span>> + Clone {
+    comment().padded().repeated().padded().ignored()
+}
+
+/// Parses the mandatory `<?php` + `declare(strict_types=1);` prologue.
+///
+/// The opening tag has to be the very first thing in the file and has to be followed by a whitespace.
+/// Whitespace and comments are allowed anywhere inside the `declare` statement.
+fn prologue() -> impl Parser<char, (Token, Span), Error = Simple<char, Span>> {
+    let trivia = skip_trivia();
+
+    let bom = just('\u{FEFF}').validate(|_, span, emit| {
+        emit(warn!(
+            span,
+            "the byte order mark in front of `<?php` is ignored"
+        ))
+    });
+
+    let open_tag = just("<?php").map_err(|e: Simple<char, Span>| {
+        error!(e.span(), "a program has to start with the `<?php` prologue")
+    });
+
+    let separator = filter(|c: &char| c.is_whitespace()).map_err(|e: Simple<char, Span>| {
+        error!(e.span(), "`<?php` has to be followed by a whitespace")
+    });
+
+    let declare = text::keyword("declare")
+        .then(just('(').padded_by(trivia.clone()))
+        .then(text::keyword("strict_types"))
+        .then(just('=').padded_by(trivia.clone()))
+        .then(just('1'))
+        .then(just(')').padded_by(trivia.clone()))
+        .then(just(';'))
+        .map_err(|e: Simple<char, Span>| {
+            error!(
+                e.span(),
+                "expected `declare(strict_types=1);` after the `<?php` prologue"
+            )
+        });
+
+    bom.or_not()
+        .then(open_tag)
+        .then(separator)
+        .then(trivia)
+        .then(declare)
+        .to(Token::Prologue)
+        .map_with_span(|tok, span| (tok, span))
+        .labelled("prologue")
+        .recover_with(skip_until([';'], |span| (Token::Error, span)).consume_end())
+}
+
+/// Parses the optional `?>` epilogue.
+///
+/// Nothing but a single newline may follow the epilogue, so it consumes the rest of the input.
+fn epilogue() -> impl Parser<char, Token, Error = Simple<char, Span>> {
+    just("?>")
         .ignore_then(
-            filter(|c| *c != '\n' && *c != '\"' && *c != '\\')
-                .or(escape)
-                .repeated(),
+            any()
+                .repeated()
+                .map_with_span(|rest: Vec<char>, span| (rest, span)),
         )
-        .then_ignore(just('"'))
-        .collect::<String>()
-        .labelled("string")
-        .map(Token::Str);
+        .validate(|(rest, span), _, emit| {
+            if !(rest.is_empty() || rest == ['\n']) {
+                emit(error!(
+                    span,
+                    "only a single newline may follow the `?>` epilogue"
+                ));
+            }
 
-    let ctrl = one_of("()[]{};,").map(Token::Control);
+            Token::Epilogue
+        })
+        .labelled("epilogue")
+}
+
+/// String literals of the file being lexed, shared by the string parsers
+type SharedLiterals<'src> = Rc<RefCell<Literals<'src>>>;
+
+fn token<'a>(
+    src: &'a str,
+    literals: &SharedLiterals<'a>,
+) -> impl Parser<char, Token, Error = Simple<char, Span>> + 'a {
+    let ctrl = one_of("()[]{};,:").map(Token::Control);
+
+    let ident = php_ident(src)
+        .map(|name| keyword(name).unwrap_or_else(|| Token::Ident(Symbol::intern(name))));
 
-    let php_ident = filter(|c: &char| c.is_alphabetic() || *c == '_' || *c == '$')
-        .map(Some)
-        .chain::<char, Vec<_>, _>(
-            filter(|c: &char| c.is_alphabetic() || *c == '_' || *c == '$').repeated(),
+    // Characters that can't start a token are grouped into a single error token
+    let unexpected = any()
+        .chain(
+            filter(|c: &char| {
+                c.is_ascii_punctuation() && !"()[]{};,:+-*/.=!<>?$\"&|#_".contains(*c)
+            })
+            .repeated(),
         )
-        .collect();
-
-    let ident = choice((
-        text::keyword("function").to(Token::Function),
-        text::keyword("if").to(Token::If),
-        text::keyword("else").to(Token::Else),
-        text::keyword("true").to(Token::Bool(true)),
-        text::keyword("false").to(Token::Bool(false)),
-        text::keyword("null").to(Token::Null),
-        text::keyword("while").to(Token::While),
-        text::keyword("return").to(Token::Return),
-        php_ident.map(Token::Ident),
-    ));
-
-    let token = num
-        .or(string)
-        .or(operator)
-        .or(ctrl)
-        .or(ident)
-        .recover_with(skip_then_retry_until([]));
-
-    let comment = just("//")
-        .then(take_until(just("\n")))
-        .or(just("/*").then(take_until(just("*/"))))
-        .padded();
-
-    token
+        .collect::<String>()
+        .validate(|chars, span, emit| {
+            emit(error!(span, format!("unexpected characters `{chars}`")));
+            Token::Error
+        });
+
+    // `choice` takes the first alternative that succeeds even if it emitted errors
+    choice((
+        epilogue(),
+        nullable_type(src),
+        number(),
+        string(src, literals),
+        heredoc(src, literals),
+        operator(),
+        ctrl,
+        variable(src),
+        ident,
+        unexpected,
+    ))
+}
+
+/// Lexes the [stream](SourceFile::stream) of the file, names are interned straight from its source
+pub fn lexer(
+    file: &SourceFile,
+) -> impl Parser<char, (Vec<(Token, Span)>, Literals<'_>), Error = Simple<char, Span>> + '_ {
+    let literals = Rc::new(RefCell::new(Literals::new(file.src())));
+
+    let tokens = token(file.src(), &literals)
         .map_with_span(|tok, span| (tok, span))
-        .padded_by(comment.repeated())
-        .padded()
+        .padded_by(skip_trivia())
+        .repeated();
+
+    prologue()
+        .chain(tokens)
+        .then_ignore(skip_trivia())
+        .then_ignore(end())
+        .map(move |tokens| (tokens, literals.replace(Literals::new(file.src()))))
+}
+
+/// The available lexer implementations, both produce the same tokens and diagnostics
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum LexerKind {
+    #[default]
+    Combinator,
+    Fast,
+}
+
+/// The tokens and their string literals, if the prologue could be lexed, and all the reported diagnostics
+pub type LexResult<'src> = (
+    Option<(Vec<(Token, Span)>, Literals<'src>)>,
+    Vec<Simple<char, Span>>,
+);
+
+/// Lexes the file with the given lexer, a file that isn't valid UTF-8 isn't lexed at all
+pub fn lex(file: &SourceFile, kind: LexerKind) -> LexResult<'_> {
+    if !file.invalid_utf8().is_empty() {
+        let errs = file
+            .invalid_utf8()
+            .iter()
+            .map(|span| {
+                error!(
+                    span.clone(),
+                    "invalid UTF-8, the source has to be UTF-8 encoded"
+                )
+            })
+            .collect();
+
+        return (None, errs);
+    }
+
+    match kind {
+        LexerKind::Combinator => lexer(file).parse_recovery(file.stream()),
+        LexerKind::Fast => fast_lexer(file),
+    }
+}
+
+/// A lexer that keeps whitespace and comments.
+///
+/// The spans of the lexemes cover the whole input without any gaps,
+/// so the source can be reconstructed from them.
+pub fn lossless_lexer(
+    file: &SourceFile,
+) -> impl Parser<char, (Vec<(Lexeme, Span)>, Literals<'_>), Error = Simple<char, Span>> + '_ {
+    let literals = Rc::new(RefCell::new(Literals::new(file.src())));
+
+    let whitespace = filter(|c: &char| c.is_whitespace())
         .repeated()
+        .at_least(1)
+        .to(Trivia::Whitespace);
+
+    let lexemes = choice((
+        comment().or(whitespace).map(Lexeme::Trivia),
+        token(file.src(), &literals).map(Lexeme::Token),
+    ))
+    .map_with_span(|lexeme, span| (lexeme, span))
+    .repeated();
+
+    prologue()
+        .map(|(tok, span)| (Lexeme::Token(tok), span))
+        .chain(lexemes)
         .then_ignore(end())
+        .map(move |lexemes| (lexemes, literals.replace(Literals::new(file.src()))))
 }
@@ -1,10 +1,26 @@
+use std::fmt::Display;
+use std::hash::Hash;
+use std::ops::Range;
+
 use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
 use chumsky::error::Simple;
 
 use crate::help::ToColor;
+use crate::source_map::{SourceFile, SourceMap};
+use crate::Span;
+
+/// Ariadne counts in chars while our spans are byte ranges
+fn char_range(file: &SourceFile, span: &Span) -> Range<usize> {
+    file.byte_to_char(span.start)..file.byte_to_char(span.end)
+}
 
-pub fn print_lexer_errors(errs: Vec<Simple<char>>, src: &str, src_name: &str) {
+/// Prints lexer or parser diagnostics, warnings are recognized by the `warn!` prefix
+pub fn print_errors<T: Display + Hash + Eq>(errs: Vec<Simple<T, Span>>, map: &SourceMap) {
     for err in errs {
+        let file = &map[err.span().file];
+        let src_name = file.name();
+        let span = char_range(file, &err.span());
+
         let kind;
         let msg = if let chumsky::error::SimpleReason::Custom(msg) = err.reason() {
             if let Some((reason, message)) = msg.split_once(':') {
@@ -47,11 +63,11 @@ pub fn print_lexer_errors(errs: Vec<Simple<char>>, src: &str, src_name: &str) {
             )
         };
 
-        let report = Report::build(kind, src_name, err.span().start)
+        let report = Report::build(kind, src_name, span.start)
             .with_code(3)
             .with_message(&msg)
             .with_label(
-                Label::new((src_name, err.span()))
+                Label::new((src_name, span))
                     .with_message(match err.reason() {
                         chumsky::error::SimpleReason::Custom(_) => msg.clone(),
                         _ => format!(
@@ -66,7 +82,7 @@ pub fn print_lexer_errors(errs: Vec<Simple<char>>, src: &str, src_name: &str) {
 
         let report = match err.reason() {
             chumsky::error::SimpleReason::Unclosed { span, delimiter } => report.with_label(
-                Label::new((src_name, span.clone()))
+                Label::new((src_name, char_range(file, span)))
                     .with_message(format!(
                         "Unclosed delimiter {}",
                         delimiter.fg(Color::Yellow)
@@ -79,7 +95,7 @@ pub fn print_lexer_errors(errs: Vec<Simple<char>>, src: &str, src_name: &str) {
 
         report
             .finish()
-            .print((src_name, Source::from(&src)))
+            .eprint((src_name, Source::from(file.src())))
             .unwrap();
     }
 }
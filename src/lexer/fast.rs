@@ -0,0 +1,1010 @@
+use std::cmp::Reverse;
+
+use chumsky::error::{Error, Simple};
+
+use super::number::{validate_number, Number};
+use super::string::{is_closing_marker, merge, remove_indentation, validate_escape, Escape, Piece};
+use super::{keyword, type_name, LexResult, OPERATORS, UNSUPPORTED_OPERATORS};
+use crate::source_map::{FileId, SourceFile};
+use crate::token::{Literals, Operator, Token};
+use crate::{error, warn, Span, Symbol, WithSpan};
+
+/// Decodes the character at the start of the text and its length in bytes.
+///
+/// A `\r\n` line ending is decoded as a single `\n` just like [`SourceFile::stream`] does.
+fn decode(text: &str) -> Option<(char, usize)> {
+    if text.starts_with("\r\n") {
+        return Some(('\n', 2));
+    }
+
+    text.chars().next().map(|c| (c, c.len_utf8()))
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()
+}
+
+/// Characters string pieces are lexed from, either the source itself or a heredoc body
+trait Chars {
+    fn peek_nth(&self, n: usize) -> Option<char>;
+
+    fn bump(&mut self);
+
+    /// Start of the next character's span
+    fn start(&self) -> usize;
+
+    /// End of the last consumed character's span
+    fn end(&self) -> usize;
+
+    /// Span from the given start to the end of the last consumed character
+    fn span(&self, start: usize) -> Span;
+
+    fn peek(&self) -> Option<char> {
+        self.peek_nth(0)
+    }
+}
+
+/// A byte position in the source
+#[derive(Debug, Copy, Clone)]
+struct Cursor<'a> {
+    /// Invalid UTF-8 was replaced when the file was loaded, so the source can be sliced
+    src: &'a str,
+    pos: usize,
+    file: FileId,
+}
+
+impl<'a> Cursor<'a> {
+    fn byte(&self, n: usize) -> Option<u8> {
+        self.src.as_bytes().get(self.pos + n).copied()
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.src[self.pos..].starts_with(prefix)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+    }
+
+    /// Text between the given position and the cursor
+    fn text_since(&self, pos: usize) -> &'a str {
+        &self.src[pos..self.pos]
+    }
+
+    fn php_ident(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        if !self.peek().is_some_and(is_ident_start) {
+            return None;
+        }
+
+        self.eat_while(is_ident_char);
+        Some(self.text_since(start))
+    }
+}
+
+impl Chars for Cursor<'_> {
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        let mut pos = self.pos;
+        for _ in 0..n {
+            pos += decode(&self.src[pos..])?.1;
+        }
+
+        decode(&self.src[pos..]).map(|(c, _)| c)
+    }
+
+    fn bump(&mut self) {
+        if let Some((_, len)) = decode(&self.src[self.pos..]) {
+            self.pos += len;
+        }
+    }
+
+    fn start(&self) -> usize {
+        self.pos
+    }
+
+    fn end(&self) -> usize {
+        self.pos
+    }
+
+    fn span(&self, start: usize) -> Span {
+        Span::new(self.file, start..self.pos)
+    }
+
+    // Most of the source is ASCII, there's no need to decode it
+    fn peek(&self) -> Option<char> {
+        match self.byte(0)? {
+            byte if byte.is_ascii() && byte != b'\r' => Some(byte as char),
+            _ => self.peek_nth(0),
+        }
+    }
+}
+
+/// A heredoc body with its indentation removed, the characters keep their original spans
+struct Body {
+    chars: Vec<(char, Span)>,
+    next: usize,
+    eoi: Span,
+}
+
+impl Chars for Body {
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.chars.get(self.next + n).map(|(c, _)| *c)
+    }
+
+    fn bump(&mut self) {
+        self.next += 1;
+    }
+
+    fn start(&self) -> usize {
+        self.chars
+            .get(self.next)
+            .map_or(self.eoi.start, |(_, span)| span.start)
+    }
+
+    fn end(&self) -> usize {
+        self.chars
+            .get(self.next.saturating_sub(1))
+            .map_or(self.eoi.end, |(_, span)| span.end)
+    }
+
+    fn span(&self, start: usize) -> Span {
+        Span::new(self.eoi.file, start..self.end())
+    }
+}
+
+/// Lexes an escape sequence, fails without consuming anything if there's a newline after the `\`
+fn escape(
+    chars: &mut impl Chars,
+    heredoc: bool,
+    emit: &mut dyn FnMut(Simple<char, Span>),
+) -> Option<Vec<char>> {
+    let start = chars.start();
+    let next = chars.peek_nth(1)?;
+
+    let take = |chars: &mut dyn Chars, f: &dyn Fn(char) -> bool, max: usize| {
+        let mut taken = String::new();
+        while taken.len() < max && chars.peek().is_some_and(f) {
+            taken.extend(chars.peek());
+            chars.bump();
+        }
+
+        taken
+    };
+
+    let simple = match next {
+        '\\' | '$' => Some(next),
+        '"' if !heredoc => Some(next),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        'v' => Some('\x0B'),
+        'e' => Some('\x1B'),
+        'f' => Some('\x0C'),
+        _ => None,
+    };
+
+    let escape = if let Some(c) = simple {
+        chars.bump();
+        chars.bump();
+        Escape::Char(c)
+    } else if next.is_digit(8) {
+        chars.bump();
+        Escape::Octal(take(chars, &|c| c.is_digit(8), 3))
+    } else if next == 'x' && chars.peek_nth(2).is_some_and(|c| c.is_ascii_hexdigit()) {
+        chars.bump();
+        chars.bump();
+        Escape::Hex(take(chars, &|c| c.is_ascii_hexdigit(), 2))
+    } else if next == 'u' && chars.peek_nth(2) == Some('{') {
+        chars.bump();
+        chars.bump();
+        chars.bump();
+        let digits = take(chars, &|c| c.is_ascii_hexdigit(), usize::MAX);
+        let closed = chars.peek() == Some('}');
+        if closed {
+            chars.bump();
+        }
+
+        Escape::Unicode { digits, closed }
+    } else if next != '\n' {
+        chars.bump();
+        chars.bump();
+        Escape::Unknown(next)
+    } else {
+        return None;
+    };
+
+    let span = chars.span(start);
+    Some(validate_escape(escape, span, &mut |err| {
+        emit(err.with_label("escape sequence"))
+    }))
+}
+
+/// Lexes the contents of a double quoted string or a heredoc, variable names are sliced out of the source
+fn pieces(
+    src: &str,
+    chars: &mut impl Chars,
+    heredoc: bool,
+    emit: &mut dyn FnMut(Simple<char, Span>),
+) -> Vec<Piece> {
+    let is_literal = |c: char| !matches!(c, '\\' | '$') && (heredoc || !matches!(c, '\n' | '"'));
+
+    let mut pieces = Vec::new();
+
+    while let Some(c) = chars.peek() {
+        if is_literal(c) && !heredoc {
+            let start = chars.start();
+            while chars.peek().is_some_and(is_literal) {
+                chars.bump();
+            }
+
+            pieces.push(Piece::Source(chars.span(start)));
+        } else if is_literal(c) {
+            // The indentation is already removed from a heredoc body, so its characters aren't a slice of the source
+            let mut literal = Vec::new();
+            while let Some(c) = chars.peek().filter(|c| is_literal(*c)) {
+                literal.push(c);
+                chars.bump();
+            }
+
+            pieces.push(Piece::Chars(literal));
+        } else if c == '\\' {
+            let escaped = escape(chars, heredoc, emit).unwrap_or_else(|| {
+                chars.bump();
+                vec!['\\']
+            });
+
+            pieces.push(Piece::Chars(escaped));
+        } else if c == '$' && chars.peek_nth(1).is_some_and(is_ident_start) {
+            let start = chars.start();
+            chars.bump();
+
+            while chars.peek().is_some_and(is_ident_char) {
+                chars.bump();
+            }
+
+            let span = chars.span(start);
+            let name = Symbol::intern(&src[span.start + '$'.len_utf8()..span.end]);
+            pieces.push(Piece::Var(WithSpan(span, name)));
+        } else if c == '$' {
+            chars.bump();
+            pieces.push(Piece::Chars(vec!['$']));
+        } else {
+            break;
+        }
+    }
+
+    pieces
+}
+
+/// The closest the prologue got to parsing, reported the same way chumsky reports it
+#[derive(Debug, Clone)]
+struct Failure {
+    at: usize,
+    span: Span,
+}
+
+impl Failure {
+    /// The failure that got further wins, the later one wins a tie
+    fn max(self, other: Option<Failure>) -> Failure {
+        match other {
+            Some(other) if other.at > self.at => other,
+            _ => self,
+        }
+    }
+}
+
+struct Lexer<'a> {
+    cursor: Cursor<'a>,
+    operators: Vec<(&'static str, Operator, Option<&'static str>)>,
+    literals: Literals<'a>,
+    errs: Vec<Simple<char, Span>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str, file: FileId) -> Self {
+        let mut operators = OPERATORS
+            .iter()
+            .map(|op| (op.as_str(), *op, None))
+            .chain(
+                UNSUPPORTED_OPERATORS
+                    .iter()
+                    .map(|(seq, op, msg)| (*seq, *op, Some(*msg))),
+            )
+            .collect::<Vec<_>>();
+
+        // Same order as the combinator lexer tries them in
+        operators.sort_by_key(|(seq, ..)| Reverse(seq.len()));
+
+        Self {
+            cursor: Cursor { src, pos: 0, file },
+            operators,
+            literals: Literals::new(src),
+            errs: Vec::new(),
+        }
+    }
+
+    /// Span of the character at the byte position or the end of input past the last one
+    fn span_at(&self, pos: usize) -> Span {
+        let len =
+            decode(&self.cursor.src[pos.min(self.cursor.src.len())..]).map_or(0, |(_, len)| len);
+
+        Span::new(self.cursor.file, pos..pos + len)
+    }
+
+    fn emit(&mut self, err: Simple<char, Span>, label: &'static str) {
+        self.errs.push(err.with_label(label));
+    }
+
+    fn comment(&mut self) -> bool {
+        let start = self.cursor.pos;
+
+        if self.cursor.starts_with("//") || self.cursor.starts_with("#") {
+            self.cursor.eat_while(|c| c != '\n');
+        } else if self.cursor.starts_with("/*") {
+            self.cursor.bump();
+            self.cursor.bump();
+
+            loop {
+                if self.cursor.starts_with("*/") {
+                    self.cursor.bump();
+                    self.cursor.bump();
+                    break;
+                }
+
+                if self.cursor.peek().is_none() {
+                    let span = self.cursor.span(start);
+                    self.errs.push(error!(span, "unterminated block comment"));
+                    break;
+                }
+
+                self.cursor.bump();
+            }
+        } else {
+            return false;
+        }
+
+        true
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            self.cursor.eat_while(char::is_whitespace);
+
+            if !self.comment() {
+                break;
+            }
+        }
+    }
+
+    /// Where skipping trivia gave up, a lone `/` gets as far as the character after it
+    fn trivia_failure(&self) -> Failure {
+        let at = self.cursor.pos + usize::from(self.cursor.byte(0) == Some(b'/'));
+
+        Failure {
+            at,
+            span: self.span_at(at),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Failure> {
+        if self.cursor.eat(c) {
+            Ok(())
+        } else {
+            Err(Failure {
+                at: self.cursor.pos,
+                span: self.span_at(self.cursor.pos),
+            })
+        }
+    }
+
+    /// Matches an ASCII identifier like `text::keyword`, returns where the identifier ended
+    fn keyword(&mut self, keyword: &str) -> Result<Failure, Failure> {
+        let start = self.cursor.pos;
+
+        if !self
+            .cursor
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return Err(Failure {
+                at: start,
+                span: self.span_at(start),
+            });
+        }
+
+        self.cursor
+            .eat_while(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        let end = self.cursor.pos;
+        if self.cursor.text_since(start) != keyword {
+            return Err(Failure {
+                at: end,
+                span: Span::new(self.cursor.file, start..end),
+            });
+        }
+
+        Ok(Failure {
+            at: end,
+            span: self.span_at(end),
+        })
+    }
+
+    /// Expects a character surrounded by trivia, the trivia's failures are kept as alternatives
+    fn padded(&mut self, c: char, alt: &mut Option<Failure>) -> Result<(), Failure> {
+        self.skip_trivia();
+        *alt = Some(self.trivia_failure().max(alt.take()));
+
+        self.expect(c).map_err(|failure| failure.max(alt.clone()))?;
+
+        self.skip_trivia();
+        *alt = Some(self.trivia_failure().max(alt.take()));
+        Ok(())
+    }
+
+    fn declare(&mut self) -> Result<(), Failure> {
+        let mut alt = Some(self.keyword("declare")?);
+
+        self.padded('(', &mut alt)?;
+
+        let keyword_end = self
+            .keyword("strict_types")
+            .map_err(|failure| failure.max(alt.clone()))?;
+        alt = Some(keyword_end.max(alt));
+
+        self.padded('=', &mut alt)?;
+        self.expect('1')
+            .map_err(|failure| failure.max(alt.clone()))?;
+        self.padded(')', &mut alt)?;
+        self.expect(';').map_err(|failure| failure.max(alt))
+    }
+
+    fn prologue_failure(&mut self) -> Option<Simple<char, Span>> {
+        if self.cursor.starts_with("\u{FEFF}") {
+            self.cursor.bump();
+            self.emit(
+                warn!(
+                    self.cursor.span(0),
+                    "the byte order mark in front of `<?php` is ignored"
+                ),
+                "prologue",
+            );
+        }
+
+        for expected in "<?php".chars() {
+            if self.cursor.peek() != Some(expected) {
+                return Some(error!(
+                    self.span_at(self.cursor.pos),
+                    "a program has to start with the `<?php` prologue"
+                ));
+            }
+
+            self.cursor.bump();
+        }
+
+        if !self.cursor.peek().is_some_and(char::is_whitespace) {
+            return Some(error!(
+                self.span_at(self.cursor.pos),
+                "`<?php` has to be followed by a whitespace"
+            ));
+        }
+
+        self.cursor.bump();
+        self.skip_trivia();
+
+        let trivia = self.trivia_failure();
+        // The character after a lone `/`
+        let found = self.cursor.peek_nth(1);
+
+        match self.declare() {
+            Ok(()) => None,
+            // A lone `/` in front of `declare` gets further than `declare` itself
+            Err(failure) if trivia.at > failure.at => Some(Simple::expected_input_found(
+                trivia.span,
+                [Some('/'), Some('*')],
+                found,
+            )),
+            Err(failure) => Some(error!(
+                failure.span,
+                "expected `declare(strict_types=1);` after the `<?php` prologue"
+            )),
+        }
+    }
+
+    /// Lexes the prologue, recovers by skipping everything up to the first `;`
+    fn prologue(&mut self) -> Option<(Token, Span)> {
+        let errs = self.errs.len();
+
+        let Some(err) = self.prologue_failure() else {
+            return Some((Token::Prologue, self.cursor.span(0)));
+        };
+
+        self.errs.push(err);
+        for err in &mut self.errs[errs..] {
+            *err = err.clone().with_label("prologue");
+        }
+
+        self.cursor.pos = 0;
+
+        if self.cursor.peek().is_none() {
+            let eoi = self.span_at(0);
+            self.errs
+                .push(Simple::expected_input_found(eoi, [Some(';')], None));
+            return None;
+        }
+
+        while let Some(c) = self.cursor.peek() {
+            self.cursor.bump();
+            if c == ';' {
+                break;
+            }
+        }
+
+        Some((Token::Error, self.cursor.span(0)))
+    }
+
+    fn epilogue(&mut self) -> Token {
+        self.cursor.bump();
+        self.cursor.bump();
+
+        let start = self.cursor.pos;
+        let rest = &self.cursor.src[self.cursor.pos..];
+        while self.cursor.peek().is_some() {
+            self.cursor.bump();
+        }
+
+        if !(rest.is_empty() || rest == "\n" || rest == "\r\n") {
+            let span = self.cursor.span(start);
+            self.emit(
+                error!(span, "only a single newline may follow the `?>` epilogue"),
+                "epilogue",
+            );
+        }
+
+        Token::Epilogue
+    }
+
+    fn nullable_type(&mut self) -> Option<Token> {
+        let start = self.cursor;
+        self.cursor.bump();
+
+        let Some(r#type) = self.cursor.php_ident().and_then(type_name) else {
+            self.cursor = start;
+            return None;
+        };
+
+        let nullable = r#type.to_nullable().unwrap_or_else(|| {
+            let span = self.cursor.span(start.pos);
+            self.emit(
+                error!(span, "a void return value is non nullable"),
+                "nullable type",
+            );
+            r#type
+        });
+
+        Some(Token::Type(nullable))
+    }
+
+    /// Lexes digits of the given radix, `_` separators are allowed only between two digits
+    fn digits(&mut self, radix: u32) -> Option<String> {
+        if !self.cursor.peek().is_some_and(|c| c.is_digit(radix)) {
+            return None;
+        }
+
+        let mut digits = String::new();
+        loop {
+            match self.cursor.peek() {
+                Some(c) if c.is_digit(radix) => {
+                    digits.push(c);
+                    self.cursor.bump();
+                }
+                Some('_') if self.cursor.peek_nth(1).is_some_and(|c| c.is_digit(radix)) => {
+                    self.cursor.bump();
+                }
+                _ => break Some(digits),
+            }
+        }
+    }
+
+    /// Lexes an exponent, nothing is consumed if it isn't followed by digits
+    fn exponent(&mut self) -> Option<String> {
+        let start = self.cursor;
+
+        let mut exponent = String::new();
+        exponent.extend(self.cursor.peek().filter(|c| matches!(c, 'e' | 'E')));
+        if exponent.is_empty() {
+            return None;
+        }
+        self.cursor.bump();
+
+        if let Some(sign) = self.cursor.peek().filter(|c| matches!(c, '+' | '-')) {
+            exponent.push(sign);
+            self.cursor.bump();
+        }
+
+        match self.digits(10) {
+            Some(digits) => Some(exponent + &digits),
+            None => {
+                self.cursor = start;
+                None
+            }
+        }
+    }
+
+    fn number_literal(&mut self) -> Number {
+        for (prefix, radix) in [('x', 16), ('o', 8), ('b', 2)] {
+            let start = self.cursor;
+            let prefixed = self.cursor.eat('0')
+                && self
+                    .cursor
+                    .peek()
+                    .is_some_and(|c| c.to_ascii_lowercase() == prefix);
+
+            if prefixed {
+                self.cursor.bump();
+                if let Some(digits) = self.digits(radix) {
+                    return Number::Int { digits, radix };
+                }
+            }
+
+            self.cursor = start;
+        }
+
+        // `1.`, `1.5` and `.5` are all valid floats
+        let start = self.cursor;
+        let fraction = match self.digits(10) {
+            Some(digits) if self.cursor.eat('.') => {
+                Some(digits + "." + &self.digits(10).unwrap_or_default())
+            }
+            Some(_) => None,
+            None => {
+                self.cursor.eat('.');
+                self.digits(10).map(|digits| format!(".{digits}"))
+            }
+        };
+
+        if let Some(float) = fraction {
+            return Number::Float(float + &self.exponent().unwrap_or_default());
+        }
+
+        self.cursor = start;
+        let digits = self.digits(10);
+        if let Some(float) = digits.and_then(|digits| Some(digits + &self.exponent()?)) {
+            return Number::Float(float);
+        }
+
+        self.cursor = start;
+
+        // Can't fail, the number starts with a digit or a dot followed by one
+        let digits = self.digits(10).unwrap_or_default();
+
+        // A leading zero makes the literal octal
+        match digits.strip_prefix('0') {
+            Some(octal) if !octal.is_empty() => Number::Int {
+                digits: octal.to_string(),
+                radix: 8,
+            },
+            _ => Number::Int { digits, radix: 10 },
+        }
+    }
+
+    fn number(&mut self) -> Token {
+        let start = self.cursor.pos;
+        let number = self.number_literal();
+
+        // Anything that could continue the literal makes it malformed
+        let junk_start = self.cursor.pos;
+        let junk = self
+            .cursor
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_');
+        self.cursor
+            .eat_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        let junk = junk.map(|first| (first, self.cursor.span(junk_start)));
+
+        let span = self.cursor.span(start);
+        let mut errs = Vec::new();
+        let token = validate_number(number, junk, span, &mut |err| errs.push(err));
+
+        for err in errs {
+            self.emit(err, "number");
+        }
+
+        token
+    }
+
+    fn string(&mut self) -> Token {
+        let start = self.cursor.pos;
+        self.cursor.bump();
+
+        // A string without escapes and variables is a slice of the source, nothing has to be collected
+        let rest = &self.cursor.src[self.cursor.pos..];
+        if let Some(len) = rest
+            .find(['\\', '$', '\n', '"'])
+            .filter(|len| rest[*len..].starts_with('"'))
+        {
+            let span = Span::new(self.cursor.file, self.cursor.pos..self.cursor.pos + len);
+            self.cursor.pos += len + '"'.len_utf8();
+            return Token::Str(self.literals.source(&span));
+        }
+
+        let src = self.cursor.src;
+        let pieces = pieces(src, &mut self.cursor, false, &mut |err| self.errs.push(err));
+
+        // An unterminated string is closed at the end of the line
+        if !self.cursor.eat('"') {
+            let span = self.cursor.span(start);
+            self.emit(
+                error!(
+                    span,
+                    "unterminated string, expected a `\"` before the end of the line"
+                ),
+                "string",
+            );
+        }
+
+        merge(pieces, &mut self.literals)
+    }
+
+    /// Lexes the `<<<EOT` opening of a heredoc, returns the label and whether it's a nowdoc
+    fn heredoc_opening(&mut self) -> Option<(&'a str, bool)> {
+        for _ in 0..3 {
+            self.cursor.bump();
+        }
+
+        self.cursor.eat_while(|c| c == ' ' || c == '\t');
+
+        let label = if self.cursor.eat('\'') {
+            let label = self.cursor.php_ident()?;
+            self.cursor.eat('\'').then_some((label, true))
+        } else if self.cursor.eat('"') {
+            let label = self.cursor.php_ident()?;
+            self.cursor.eat('"').then_some((label, false))
+        } else {
+            self.cursor.php_ident().map(|label| (label, false))
+        };
+
+        label.filter(|_| self.cursor.eat('\n'))
+    }
+
+    /// Lexes a heredoc body line, fails on the closing marker and on a line without a newline
+    fn heredoc_line(&mut self, label: &str) -> Option<Vec<(char, Span)>> {
+        let start = self.cursor;
+
+        let mut line = Vec::new();
+        loop {
+            let Some(c) = self.cursor.peek() else {
+                self.cursor = start;
+                return None;
+            };
+
+            let pos = self.cursor.pos;
+            self.cursor.bump();
+            line.push((c, self.cursor.span(pos)));
+
+            if c == '\n' {
+                break;
+            }
+        }
+
+        if is_closing_marker(&line, label) {
+            self.cursor = start;
+            return None;
+        }
+
+        Some(line)
+    }
+
+    fn heredoc(&mut self) -> Option<Token> {
+        let start = self.cursor;
+
+        let Some((label, nowdoc)) = self.heredoc_opening() else {
+            self.cursor = start;
+            return None;
+        };
+
+        let mut lines = Vec::new();
+        while let Some(line) = self.heredoc_line(label) {
+            lines.push(line);
+        }
+
+        let closing = self.cursor;
+        let mut indentation = Vec::new();
+        while let Some(c) = self.cursor.peek().filter(|c| *c == ' ' || *c == '\t') {
+            indentation.push(c);
+            self.cursor.bump();
+        }
+
+        let closed = self.cursor.starts_with(label);
+        if closed {
+            for _ in label.chars() {
+                self.cursor.bump();
+            }
+        } else {
+            // An unterminated heredoc swallows the rest of the file
+            self.cursor = closing;
+            while self.cursor.peek().is_some() {
+                self.cursor.bump();
+            }
+        }
+
+        let span = self.cursor.span(start.pos);
+        let mut errs = Vec::new();
+        let mut emit = |err: Simple<char, Span>| errs.push(err);
+
+        if !closed {
+            indentation.clear();
+            emit(error!(
+                span.clone(),
+                "unterminated heredoc, the closing marker is missing"
+            ));
+        }
+
+        if indentation.contains(&' ') && indentation.contains(&'\t') {
+            emit(error!(
+                span.clone(),
+                "invalid indentation, tabs and spaces can't be mixed"
+            ));
+        }
+
+        let body = remove_indentation(lines, &indentation, &mut emit);
+
+        for err in errs {
+            self.emit(err, "heredoc");
+        }
+
+        if nowdoc {
+            let start = self.literals.start();
+            self.literals.append(body.into_iter().map(|(c, _)| c));
+            return Some(Token::Str(self.literals.finish(start)));
+        }
+
+        let eoi = body.last().map_or(span.clone(), |(_, span)| span.after());
+        let mut body = Body {
+            chars: body,
+            next: 0,
+            eoi,
+        };
+
+        let pieces = pieces(self.cursor.src, &mut body, true, &mut |err| {
+            self.errs.push(err)
+        });
+        Some(merge(pieces, &mut self.literals))
+    }
+
+    fn operator(&mut self) -> Option<Token> {
+        let (seq, op, msg) = *self
+            .operators
+            .iter()
+            .find(|(seq, ..)| self.cursor.starts_with(seq))?;
+
+        let start = self.cursor.pos;
+        for _ in 0..seq.len() {
+            self.cursor.bump();
+        }
+
+        if let Some(msg) = msg {
+            let span = self.cursor.span(start);
+            self.emit(error!(span, msg), "operator");
+        }
+
+        Some(Token::Op(op))
+    }
+
+    fn variable(&mut self) -> Token {
+        let start = self.cursor.pos;
+        self.cursor.bump();
+
+        let name = self.cursor.php_ident().unwrap_or_else(|| {
+            let span = self.cursor.span(start);
+            self.emit(error!(span, "`$` must be followed by a name"), "variable");
+            ""
+        });
+
+        Token::Var(Symbol::intern(name))
+    }
+
+    /// Characters that can't start a token are grouped into a single error token
+    fn unexpected(&mut self) -> Token {
+        let start = self.cursor.pos;
+        self.cursor.bump();
+
+        self.cursor
+            .eat_while(|c| c.is_ascii_punctuation() && !"()[]{};,:+-*/.=!<>?$\"&|#_".contains(c));
+
+        let chars = self.cursor.text_since(start);
+        let span = self.cursor.span(start);
+        self.errs
+            .push(error!(span, format!("unexpected characters `{chars}`")));
+
+        Token::Error
+    }
+
+    /// Lexes a token, tries the same alternatives in the same order as the combinator lexer
+    fn token(&mut self) -> Token {
+        if self.cursor.starts_with("?>") {
+            return self.epilogue();
+        }
+
+        if self.cursor.starts_with("?") {
+            if let Some(token) = self.nullable_type() {
+                return token;
+            }
+        }
+
+        let c = self.cursor.peek();
+        let dot_digit =
+            c == Some('.') && self.cursor.peek_nth(1).is_some_and(|c| c.is_ascii_digit());
+        if c.is_some_and(|c| c.is_ascii_digit()) || dot_digit {
+            return self.number();
+        }
+
+        if c == Some('"') {
+            return self.string();
+        }
+
+        if self.cursor.starts_with("<<<") {
+            if let Some(token) = self.heredoc() {
+                return token;
+            }
+        }
+
+        if let Some(token) = self.operator() {
+            return token;
+        }
+
+        match c {
+            Some(c) if "()[]{};,:".contains(c) => {
+                self.cursor.bump();
+                Token::Control(c)
+            }
+            Some('$') => self.variable(),
+            Some(c) if is_ident_start(c) => {
+                // Can't fail, the identifier's first character is already known
+                let name = self.cursor.php_ident().unwrap_or_default();
+                keyword(name).unwrap_or_else(|| Token::Ident(Symbol::intern(name)))
+            }
+            _ => self.unexpected(),
+        }
+    }
+}
+
+/// A hand written lexer producing the same tokens and diagnostics as [`lexer`](super::lexer).
+///
+/// It doesn't need to build any parsers and works directly on bytes, which makes it a lot faster.
+pub fn fast_lexer(file: &SourceFile) -> LexResult<'_> {
+    let mut lexer = Lexer::new(file.src(), file.id());
+
+    let Some(prologue) = lexer.prologue() else {
+        return (None, lexer.errs);
+    };
+
+    let mut tokens = vec![prologue];
+    loop {
+        lexer.skip_trivia();
+
+        if lexer.cursor.peek().is_none() {
+            break;
+        }
+
+        let start = lexer.cursor.pos;
+        let token = lexer.token();
+        tokens.push((token, lexer.cursor.span(start)));
+    }
+
+    (Some((tokens, lexer.literals)), lexer.errs)
+}
@@ -0,0 +1,131 @@
+use std::num::IntErrorKind;
+
+use chumsky::prelude::*;
+
+use crate::token::{Float, Token};
+use crate::{error, unreachable, Span};
+
+/// A numeric literal with its separators removed
+#[derive(Debug, Clone)]
+pub(super) enum Number {
+    Int { digits: String, radix: u32 },
+    Float(String),
+}
+
+/// Parses digits of the given radix, `_` separators are allowed only between two digits
+fn digits(radix: u32) -> impl Parser<char, String, Error = Simple<char, Span>> + Clone {
+    filter(move |c: &char| c.is_digit(radix))
+        .chain::<char, Vec<_>, _>(
+            just('_')
+                .or_not()
+                .ignore_then(filter(move |c: &char| c.is_digit(radix)))
+                .repeated(),
+        )
+        .collect()
+}
+
+fn to_token(number: Number, span: Span, emit: &mut dyn FnMut(Simple<char, Span>)) -> Token {
+    match number {
+        Number::Int { digits, radix } => match i64::from_str_radix(&digits, radix) {
+            Ok(int) => Token::Int(int),
+            Err(err) => {
+                match err.kind() {
+                    IntErrorKind::PosOverflow => emit(error!(
+                        span,
+                        "integer literal doesn't fit into a 64 bit signed integer"
+                    )),
+                    IntErrorKind::InvalidDigit => {
+                        emit(error!(span, "invalid digit in an octal literal"))
+                    }
+                    _ => emit(unreachable!(
+                        span,
+                        format!("failed to parse {digits} as an integer")
+                    )),
+                }
+
+                Token::Int(0)
+            }
+        },
+        Number::Float(float) => Token::Float(Float(float.parse().unwrap_or_else(|_| {
+            emit(unreachable!(
+                span,
+                format!("failed to parse {float} as a float")
+            ));
+            0.0
+        }))),
+    }
+}
+
+/// Reports anything that continues the literal and converts it into a token
+pub(super) fn validate_number(
+    number: Number,
+    junk: Option<(char, Span)>,
+    span: Span,
+    emit: &mut dyn FnMut(Simple<char, Span>),
+) -> Token {
+    if let Some((first, junk_span)) = junk {
+        let msg = match (first, &number) {
+            ('_', _) => "numeric literal separators have to be placed between digits".to_string(),
+            ('e' | 'E', Number::Int { radix: 10, .. }) | ('e' | 'E', Number::Float(_)) => {
+                "expected digits after the exponent".to_string()
+            }
+            ('x' | 'X' | 'o' | 'O' | 'b' | 'B', Number::Int { digits, radix: 10 })
+                if digits == "0" =>
+            {
+                format!("expected digits after the `0{first}` prefix")
+            }
+            _ => "invalid numeric literal".to_string(),
+        };
+
+        emit(error!(junk_span, msg));
+    }
+
+    to_token(number, span, emit)
+}
+
+pub fn number() -> impl Parser<char, Token, Error = Simple<char, Span>> {
+    let prefixed = |prefix, radix| {
+        just('0')
+            .ignore_then(one_of(prefix))
+            .ignore_then(digits(radix))
+            .map(move |digits| Number::Int { digits, radix })
+    };
+
+    let exp = one_of("eE")
+        .chain(one_of("+-").or_not())
+        .chain::<char, _, _>(digits(10));
+
+    // `1.`, `1.5` and `.5` are all valid floats
+    let float = digits(10)
+        .chain(just('.'))
+        .chain::<char, _, _>(digits(10).or_not().map(Option::unwrap_or_default))
+        .or(just('.').chain(digits(10)))
+        .chain::<char, _, _>(exp.clone().or_not().flatten())
+        .or(digits(10).chain(exp))
+        .collect::<String>()
+        .map(Number::Float);
+
+    // A leading zero makes the literal octal
+    let decimal = digits(10).map(|digits| match digits.strip_prefix('0') {
+        Some(octal) if !octal.is_empty() => Number::Int {
+            digits: octal.to_string(),
+            radix: 8,
+        },
+        _ => Number::Int { digits, radix: 10 },
+    });
+
+    // Anything that could continue the literal makes it malformed, `1e` isn't `1` followed by `e`
+    let junk = filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_')
+        .repeated()
+        .at_least(1)
+        .map_with_span(|junk: Vec<char>, span| (junk[0], span));
+
+    prefixed("xX", 16)
+        .or(prefixed("oO", 8))
+        .or(prefixed("bB", 2))
+        .or(float)
+        .or(decimal)
+        .then(junk.or_not())
+        .validate(|(number, junk), span, emit| validate_number(number, junk, span, emit))
+        .labelled("number")
+}
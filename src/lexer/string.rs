@@ -0,0 +1,423 @@
+use chumsky::prelude::*;
+use chumsky::Stream;
+
+use super::{php_ident, SharedLiterals};
+use crate::token::{Literals, StrId, StrPart, Token};
+use crate::{error, warn, Span, Symbol, WithSpan};
+
+/// An escape sequence before it gets validated
+#[derive(Debug, Clone)]
+pub(super) enum Escape {
+    Char(char),
+    Octal(String),
+    Hex(String),
+    Unicode {
+        digits: String,
+        closed: bool,
+    },
+    /// PHP keeps unknown escape sequences as is, backslash included
+    Unknown(char),
+}
+
+/// Converts an escape sequence into the characters it stands for
+pub(super) fn validate_escape(
+    escape: Escape,
+    span: Span,
+    emit: &mut dyn FnMut(Simple<char, Span>),
+) -> Vec<char> {
+    match escape {
+        Escape::Char(c) => vec![c],
+        Escape::Octal(digits) => {
+            // Can't fail, at most 3 octal digits always fit
+            let mut octal = u32::from_str_radix(&digits, 8).unwrap_or_default();
+            if octal > 0o377 {
+                emit(warn!(
+                    span,
+                    format!("octal escape sequence overflow, \\{digits} is greater than \\377")
+                ));
+                octal &= 0xFF;
+            }
+
+            // Bytes are mapped onto the first 256 unicode characters
+            vec![char::from_u32(octal).unwrap_or('\u{FFFD}')]
+        }
+        Escape::Hex(digits) => {
+            let hex = u32::from_str_radix(&digits, 16).unwrap_or_default();
+            vec![char::from_u32(hex).unwrap_or('\u{FFFD}')]
+        }
+        Escape::Unicode { digits, closed } => {
+            if digits.is_empty() || !closed {
+                emit(error!(span, "invalid UTF-8 codepoint escape sequence"));
+                return vec!['\u{FFFD}'];
+            }
+
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|c| vec![c])
+                .unwrap_or_else(|| {
+                    emit(error!(
+                        span,
+                        "invalid UTF-8 codepoint escape sequence, codepoint out of range"
+                    ));
+                    vec!['\u{FFFD}'] // unicode replacement character
+                })
+        }
+        Escape::Unknown(c) => {
+            let typo = match c {
+                'N' | 'R' | 'T' | 'V' | 'E' | 'F' => {
+                    Some(format!("did you mean `\\{}`?", c.to_ascii_lowercase()))
+                }
+                'u' => Some("unicode escapes are written as `\\u{...}`".to_string()),
+                'x' => Some("hex escapes need at least one hexadecimal digit".to_string()),
+                '\'' => Some("`'` doesn't need to be escaped here".to_string()),
+                _ => None,
+            };
+
+            if let Some(hint) = typo {
+                emit(warn!(
+                    span,
+                    format!("unknown escape sequence `\\{c}` is kept as is, {hint}")
+                ));
+            }
+
+            vec!['\\', c]
+        }
+    }
+}
+
+/// Parses an escape sequence the same way PHP does in double quoted strings and heredocs
+///
+/// `"` doesn't need to be escaped in heredocs so `\"` is kept as is there.
+fn escape(heredoc: bool) -> impl Parser<char, Vec<char>, Error = Simple<char, Span>> + Clone {
+    let simple = choice((
+        just('\\'),
+        filter(move |c: &char| *c == '"' && !heredoc),
+        just('$'),
+        just('n').to('\n'),
+        just('r').to('\r'),
+        just('t').to('\t'),
+        just('v').to('\x0B'), // vertical tab
+        just('e').to('\x1B'), // escape
+        just('f').to('\x0C'), // form feed
+    ))
+    .map(Escape::Char);
+
+    let octal = filter(|c: &char| c.is_digit(8))
+        .repeated()
+        .at_least(1)
+        .at_most(3)
+        .collect::<String>()
+        .map(Escape::Octal);
+
+    let hex = just('x').ignore_then(
+        filter(|c: &char| c.is_ascii_hexdigit())
+            .repeated()
+            .at_least(1)
+            .at_most(2)
+            .collect::<String>()
+            .map(Escape::Hex),
+    );
+
+    let unicode = just("u{").ignore_then(
+        filter(|c: &char| c.is_ascii_hexdigit())
+            .repeated()
+            .collect::<String>()
+            .then(just('}').or_not())
+            .map(|(digits, closed)| Escape::Unicode {
+                digits,
+                closed: closed.is_some(),
+            }),
+    );
+
+    let unknown = filter(|c: &char| *c != '\n').map(Escape::Unknown);
+
+    // Errors can't be emitted inside the alternatives, `or` would prefer the unknown escape
+    just('\\')
+        .ignore_then(simple.or(octal).or(hex).or(unicode).or(unknown))
+        .validate(validate_escape)
+        .labelled("escape sequence")
+}
+
+/// A piece of a double quoted string before neighbouring characters get merged
+#[derive(Debug, Clone)]
+pub(super) enum Piece {
+    /// Characters taken from the source as they are
+    Source(Span),
+    Chars(Vec<char>),
+    Var(WithSpan<Symbol>),
+}
+
+/// Parses the contents of a double quoted string or a heredoc
+fn pieces(
+    src: &str,
+    heredoc: bool,
+) -> impl Parser<char, Vec<Piece>, Error = Simple<char, Span>> + Clone + '_ {
+    let var = just('$')
+        .ignore_then(php_ident(src))
+        .map_with_span(|name, span| Piece::Var(WithSpan(span, Symbol::intern(name))));
+
+    let is_literal =
+        move |c: &char| !matches!(c, '\\' | '$') && (heredoc || !matches!(c, '\n' | '"'));
+
+    // The indentation is already removed from a heredoc body, so its characters aren't a slice of the source
+    let literal = if heredoc {
+        filter(is_literal)
+            .repeated()
+            .at_least(1)
+            .map(Piece::Chars)
+            .boxed()
+    } else {
+        filter(is_literal)
+            .ignored()
+            .repeated()
+            .at_least(1)
+            .map_with_span(|_, span| Piece::Source(span))
+            .boxed()
+    };
+
+    choice((
+        literal,
+        escape(heredoc).map(Piece::Chars),
+        var,
+        // A `$` that isn't followed by a name is kept as is, so is a `\` at the end of a line
+        one_of("$\\").map(|c| Piece::Chars(vec![c])),
+    ))
+    .repeated()
+}
+
+/// Stores neighbouring pieces as a single literal
+fn literal(run: &[Piece], literals: &mut Literals) -> StrId {
+    if let [Piece::Source(span)] = run {
+        return literals.source(span);
+    }
+
+    let start = literals.start();
+    for piece in run {
+        match piece {
+            Piece::Source(span) => literals.append_source(span),
+            Piece::Chars(chars) => literals.append(chars.iter().copied()),
+            Piece::Var(_) => {}
+        }
+    }
+
+    literals.finish(start)
+}
+
+/// Merges neighbouring characters, a string without any variables becomes a [`Token::Str`]
+pub(super) fn merge(pieces: Vec<Piece>, literals: &mut Literals) -> Token {
+    if !pieces.iter().any(|piece| matches!(piece, Piece::Var(_))) {
+        return Token::Str(literal(&pieces, literals));
+    }
+
+    let mut parts = Vec::new();
+
+    // Every run of characters is followed by a variable, except for the last one
+    for run in pieces.split_inclusive(|piece| matches!(piece, Piece::Var(_))) {
+        let (var, run) = match run.split_last() {
+            Some((Piece::Var(var), run)) => (Some(var), run),
+            _ => (None, run),
+        };
+
+        let lit = literal(run, literals);
+        if lit != StrId::EMPTY {
+            parts.push(StrPart::Lit(lit));
+        }
+
+        parts.extend(var.cloned().map(StrPart::Var));
+    }
+
+    Token::InterpolatedStr(parts)
+}
+
+pub fn string<'a>(
+    src: &'a str,
+    literals: &SharedLiterals<'a>,
+) -> impl Parser<char, Token, Error = Simple<char, Span>> + Clone + 'a {
+    // A string without escapes and variables is a slice of the source, nothing has to be collected
+    let plain = {
+        let literals = literals.clone();
+
+        just('"')
+            .ignore_then(
+                filter(|c: &char| !matches!(c, '\\' | '$' | '\n' | '"'))
+                    .ignored()
+                    .repeated()
+                    .map_with_span(|_, span: Span| span),
+            )
+            .then_ignore(just('"'))
+            .map(move |span| Token::Str(literals.borrow_mut().source(&span)))
+    };
+
+    // An unterminated string is closed at the end of the line
+    let end_of_line = just('\n').rewind().ignored().or(end());
+
+    let literals = literals.clone();
+    let string = just('"')
+        .ignore_then(pieces(src, false))
+        .then(just('"').to(true).or(end_of_line.to(false)))
+        .validate(move |(pieces, closed), span, emit| {
+            if !closed {
+                emit(error!(
+                    span,
+                    "unterminated string, expected a `\"` before the end of the line"
+                ));
+            }
+
+            merge(pieces, &mut literals.borrow_mut())
+        });
+
+    plain.or(string).labelled("string")
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()
+}
+
+/// Checks whether a heredoc line is the closing marker.
+///
+/// The marker may be followed by anything but an identifier character.
+pub(super) fn is_closing_marker(line: &[(char, Span)], label: &str) -> bool {
+    let line = line
+        .iter()
+        .map(|(c, _)| *c)
+        .skip_while(|c| *c == ' ' || *c == '\t')
+        .collect::<String>();
+
+    line.strip_prefix(label)
+        .is_some_and(|rest| !rest.starts_with(is_ident_char))
+}
+
+/// Removes the closing marker's indentation from every line of the heredoc body (PHP 7.3+)
+pub(super) fn remove_indentation(
+    lines: Vec<Vec<(char, Span)>>,
+    indentation: &[char],
+    emit: &mut dyn FnMut(Simple<char, Span>),
+) -> Vec<(char, Span)> {
+    let mut body = Vec::new();
+
+    for line in lines {
+        let mut chars = line.into_iter().peekable();
+
+        for indent in indentation {
+            match chars.peek() {
+                // Blank lines don't need to be indented
+                None | Some(('\n', _)) => break,
+                Some((c, span)) if *c == ' ' || *c == '\t' => {
+                    if c != indent {
+                        emit(error!(
+                            span.clone(),
+                            "invalid indentation, tabs and spaces can't be mixed"
+                        ));
+                    }
+                }
+                Some((_, span)) => {
+                    emit(error!(
+                        span.clone(),
+                        "invalid body indentation level, lines can't be indented less than the closing marker"
+                    ));
+                    break;
+                }
+            }
+
+            chars.next();
+        }
+
+        body.extend(chars);
+    }
+
+    // The newline in front of the closing marker isn't a part of the string
+    if let Some(('\n', _)) = body.last() {
+        body.pop();
+    }
+
+    body
+}
+
+/// Parses heredoc (`<<<EOT`) and nowdoc (`<<<'EOT'`) strings
+pub fn heredoc<'a>(
+    src: &'a str,
+    literals: &SharedLiterals<'a>,
+) -> impl Parser<char, Token, Error = Simple<char, Span>> + Clone + 'a {
+    let literals = literals.clone();
+
+    let label = choice((
+        php_ident(src)
+            .delimited_by(just('\''), just('\''))
+            .map(|label| (label, true)),
+        php_ident(src)
+            .delimited_by(just('"'), just('"'))
+            .map(|label| (label, false)),
+        php_ident(src).map(|label| (label, false)),
+    ));
+
+    let opening = just("<<<")
+        .ignore_then(one_of(" \t").repeated())
+        .ignore_then(label)
+        .then_ignore(just('\n'));
+
+    opening
+        .then_with(|(label, nowdoc)| {
+            let line = filter(|c: &char| *c != '\n')
+                .map_with_span(|c, span| (c, span))
+                .repeated()
+                .chain(just('\n').map_with_span(|c, span| (c, span)))
+                .try_map(move |line: Vec<(char, Span)>, span| {
+                    if is_closing_marker(&line, label) {
+                        return Err(Simple::custom(span, "unexpected closing marker"));
+                    }
+
+                    Ok(line)
+                });
+
+            let closing = one_of(" \t")
+                .repeated()
+                .then_ignore(just(label))
+                .map(Some)
+                // An unterminated heredoc swallows the rest of the file
+                .or(any().repeated().to(None));
+
+            line.repeated()
+                .then(closing)
+                .map(move |(lines, indentation)| (lines, indentation, nowdoc))
+        })
+        .validate(move |(lines, indentation, nowdoc), span: Span, emit| {
+            let indentation = indentation.unwrap_or_else(|| {
+                emit(error!(
+                    span.clone(),
+                    "unterminated heredoc, the closing marker is missing"
+                ));
+                Vec::new()
+            });
+
+            if indentation.contains(&' ') && indentation.contains(&'\t') {
+                emit(error!(
+                    span.clone(),
+                    "invalid indentation, tabs and spaces can't be mixed"
+                ));
+            }
+
+            let body = remove_indentation(lines, &indentation, emit);
+
+            let mut literals = literals.borrow_mut();
+            if nowdoc {
+                let start = literals.start();
+                literals.append(body.into_iter().map(|(c, _)| c));
+                return Token::Str(literals.finish(start));
+            }
+
+            let eoi = body.last().map_or(span.clone(), |(_, span)| span.after());
+            let (pieces, errs) = pieces(src, true)
+                .then_ignore(end())
+                .parse_recovery(Stream::from_iter(eoi, body.into_iter()));
+
+            for err in errs {
+                emit(err);
+            }
+
+            pieces.map_or(Token::Str(StrId::EMPTY), |pieces| {
+                merge(pieces, &mut literals)
+            })
+        })
+        .labelled("heredoc")
+}
@@ -1,5 +1,11 @@
+// chumsky closures have to return its `Simple` error, which carries our spans by value
+#![allow(clippy::result_large_err)]
+
 pub mod help;
 pub mod lexer;
+pub mod semantic_analysis;
+pub mod source_map;
+pub mod symbol;
 pub mod syntactic_analysis;
 pub mod token;
 
@@ -7,4 +13,5 @@ mod macros;
 
 pub use help::*;
 pub use lexer::lexer;
+pub use symbol::Symbol;
 pub use token::Token;
@@ -1,16 +1,109 @@
-use std::{env, fs};
+use std::hash::Hash;
+use std::{env, process};
 
-use chumsky::prelude::*;
-use rust_ifj22_compiler::lexer::{lexer, print_lexer_errors};
+use chumsky::error::{Simple, SimpleReason};
+use chumsky::{Parser, Stream};
+
+use rust_ifj22_compiler::lexer::{lex, print_errors, LexerKind};
+use rust_ifj22_compiler::semantic_analysis::{
+    check_loop_control, resolve_functions, FunctionTable,
+};
+use rust_ifj22_compiler::source_map::SourceMap;
+use rust_ifj22_compiler::syntactic_analysis::{parser, Extensions};
+use rust_ifj22_compiler::{Span, Token};
+
+/// Exit code IFJ22 requires for lexical errors
+const LEXICAL_ERROR: i32 = 1;
+/// Exit code IFJ22 requires for syntax errors
+const SYNTAX_ERROR: i32 = 2;
+/// Exit code IFJ22 requires for undefined and redefined functions
+const FUNCTION_ERROR: i32 = 3;
+/// Exit code IFJ22 requires for semantic errors without a code of their own
+const SEMANTIC_ERROR: i32 = 8;
+
+/// Warnings alone don't make the compilation fail
+fn has_errors<T: Hash + Eq>(errs: &[Simple<T, Span>]) -> bool {
+    errs.iter()
+        .any(|err| !matches!(err.reason(), SimpleReason::Custom(msg) if msg.starts_with("Warn:")))
+}
 
 fn main() {
-    let file_name = env::args().nth(1).expect("Expected file argument");
+    let mut kind = LexerKind::Combinator;
+    let mut extensions = Extensions::default();
+    let mut dump_ast = false;
+    let mut warn_casing = false;
+    let mut file_name = None;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--fast-lexer" => kind = LexerKind::Fast,
+            // Vanilla IFJ22, `if` requires `else` and so on
+            "--strict" => extensions = Extensions::NONE,
+            "--dump-ast" => dump_ast = true,
+            "--warn-casing" => warn_casing = true,
+            _ => file_name = Some(arg),
+        }
+    }
+
+    let file_name = file_name.expect("Expected file argument");
+
+    let mut map = SourceMap::new();
+    let file = map.load(&file_name).expect("Failed to read file");
+
+    let (tokens, lexer_errs) = lex(&map[file], kind);
+    let lexer_failed = has_errors(&lexer_errs);
+
+    // The lexer recovers with error tokens, so the syntax errors after a lexical one are reported too
+    let Some((tokens, literals)) = tokens else {
+        print_errors(lexer_errs, &map);
+        process::exit(LEXICAL_ERROR);
+    };
+
+    let stream = Stream::from_iter(map[file].eoi(), tokens.into_iter());
+    let (program, mut errs) = parser(extensions).parse_recovery(stream);
+
+    // Error tokens were already reported by the lexer
+    errs.retain(|err| err.found() != Some(&Token::Error));
+
+    if dump_ast {
+        eprintln!("{:#?}", program);
+
+        // The AST refers to string literals only by their ids
+        for (id, text) in literals.iter() {
+            eprintln!("{id:?} = {text:?}");
+        }
+    }
+
+    let failed = has_errors(&errs);
+    print_errors(lexer_errs, &map);
+    print_errors(errs, &map);
+
+    if lexer_failed {
+        process::exit(LEXICAL_ERROR);
+    }
+
+    let Some(mut program) = program.filter(|_| !failed) else {
+        process::exit(SYNTAX_ERROR);
+    };
+
+    let mut function_errs = Vec::new();
+    let table = FunctionTable::new().warn_casing(warn_casing);
+    resolve_functions(&mut program, table, &mut |err| function_errs.push(err));
+
+    let mut errs = Vec::new();
+    check_loop_control(&program, &mut |err| errs.push(err));
 
-    let src = fs::read_to_string(&file_name).expect("Failed to read file");
+    let functions_failed = has_errors(&function_errs);
+    let failed = has_errors(&errs);
+    print_errors(function_errs, &map);
+    print_errors(errs, &map);
 
-    let (tokens, errs) = lexer().parse_recovery(src.as_str());
-    println!("{:#?}", tokens);
-    //println!("{:?}", errs);
+    // Both passes are reported, the function errors decide the exit code
+    if functions_failed {
+        process::exit(FUNCTION_ERROR);
+    }
 
-    print_lexer_errors(errs, &src, &file_name);
+    if failed {
+        process::exit(SEMANTIC_ERROR);
+    }
 }
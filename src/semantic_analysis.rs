@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use chumsky::prelude::*;
+
+use crate::syntactic_analysis::ast::{Expr, ExprKind, For, If, Item, Stmt, While};
+use crate::{error, warn, Span, Symbol, Token, WithSpan};
+
+/// Functions every IFJ22 program can call without declaring them
+const BUILTINS: [&str; 11] = [
+    "reads",
+    "readi",
+    "readf",
+    "write",
+    "floatval",
+    "intval",
+    "strval",
+    "strlen",
+    "substring",
+    "ord",
+    "chr",
+];
+
+fn case_insensitive(name: Symbol) -> Symbol {
+    Symbol::intern(&name.as_str().to_ascii_lowercase())
+}
+
+#[derive(Debug, Clone)]
+struct Function {
+    name: Symbol,
+    /// Built-in functions aren't declared anywhere
+    span: Option<Span>,
+}
+
+/// Declared functions, looked up case insensitively like in PHP
+#[derive(Debug, Clone)]
+pub struct FunctionTable {
+    functions: HashMap<Symbol, Function>,
+    warn_casing: bool,
+}
+
+impl Default for FunctionTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FunctionTable {
+    /// Creates a table containing only the built-in functions
+    pub fn new() -> Self {
+        let functions = BUILTINS
+            .iter()
+            .map(|name| {
+                let function = Function {
+                    name: Symbol::intern(name),
+                    span: None,
+                };
+
+                (function.name, function)
+            })
+            .collect();
+
+        Self {
+            functions,
+            warn_casing: false,
+        }
+    }
+
+    /// Warn when a function is called with a different casing than it was declared with
+    pub fn warn_casing(mut self, warn_casing: bool) -> Self {
+        self.warn_casing = warn_casing;
+        self
+    }
+
+    /// Declares a function, functions can't be redeclared not even with a different casing
+    pub fn declare(
+        &mut self,
+        WithSpan(span, name): WithSpan<Symbol>,
+        emit: &mut dyn FnMut(Simple<Token, Span>),
+    ) {
+        let key = case_insensitive(name);
+
+        if let Some(function) = self.functions.get(&key) {
+            let msg = match function.span {
+                Some(_) => format!("function `{name}` is already declared"),
+                None => format!("function `{name}` is a built-in function"),
+            };
+
+            emit(error!(span, msg));
+            return;
+        }
+
+        self.functions.insert(
+            key,
+            Function {
+                name,
+                span: Some(span),
+            },
+        );
+    }
+
+    /// Finds the declared name of the called function
+    pub fn lookup(
+        &self,
+        WithSpan(span, name): &WithSpan<Symbol>,
+        emit: &mut dyn FnMut(Simple<Token, Span>),
+    ) -> Option<Symbol> {
+        let Some(function) = self.functions.get(&case_insensitive(*name)) else {
+            emit(error!(
+                span.clone(),
+                format!("call to undefined function `{name}`")
+            ));
+            return None;
+        };
+
+        if self.warn_casing && function.name != *name {
+            emit(warn!(
+                span.clone(),
+                format!(
+                    "`{name}` differs in casing from the declared `{}`",
+                    function.name
+                )
+            ));
+        }
+
+        Some(function.name)
+    }
+}
+
+/// Declares every function of the program and rewrites calls to the declared names
+///
+/// All functions are declared before any call is resolved, a function can be called above its definition
+pub fn resolve_functions(
+    program: &mut [Item],
+    mut table: FunctionTable,
+    emit: &mut dyn FnMut(Simple<Token, Span>),
+) {
+    for item in program.iter() {
+        if let Item::FuncDef(func) = item {
+            table.declare(func.func_ident.clone(), emit);
+        }
+    }
+
+    for item in program {
+        match item {
+            Item::FuncDef(func) => resolve_body(&table, &mut func.body, emit),
+            Item::Stmt(stmt) => resolve_stmt(&table, stmt, emit),
+        }
+    }
+}
+
+fn resolve_body(
+    table: &FunctionTable,
+    body: &mut [Stmt],
+    emit: &mut dyn FnMut(Simple<Token, Span>),
+) {
+    for stmt in body {
+        resolve_stmt(table, stmt, emit);
+    }
+}
+
+fn resolve_stmt(table: &FunctionTable, stmt: &mut Stmt, emit: &mut dyn FnMut(Simple<Token, Span>)) {
+    match stmt {
+        Stmt::If(If {
+            expr,
+            if_body,
+            else_body,
+            ..
+        }) => {
+            resolve_expr(table, expr, emit);
+            resolve_body(table, if_body, emit);
+            resolve_body(table, else_body, emit);
+        }
+        Stmt::While(While { expr, body, .. }) => {
+            resolve_expr(table, expr, emit);
+            resolve_body(table, body, emit);
+        }
+        Stmt::For(For {
+            init,
+            cond,
+            step,
+            body,
+            ..
+        }) => {
+            resolve_body(table, init, emit);
+            if let Some(cond) = cond {
+                resolve_expr(table, cond, emit);
+            }
+            resolve_body(table, step, emit);
+            resolve_body(table, body, emit);
+        }
+        Stmt::Block { body, .. } => resolve_body(table, body, emit),
+        Stmt::Exp { expr, .. }
+        | Stmt::VarAssignment {
+            expression: expr, ..
+        }
+        | Stmt::Return {
+            expr: Some(expr), ..
+        } => resolve_expr(table, expr, emit),
+        Stmt::Return { expr: None, .. }
+        | Stmt::Break { .. }
+        | Stmt::Continue { .. }
+        | Stmt::Error { .. } => {}
+    }
+}
+
+fn resolve_expr(table: &FunctionTable, expr: &mut Expr, emit: &mut dyn FnMut(Simple<Token, Span>)) {
+    match &mut expr.kind {
+        ExprKind::Call { ident, args } => {
+            if let Some(name) = table.lookup(&WithSpan(expr.span.clone(), *ident), emit) {
+                *ident = name;
+            }
+
+            for arg in args {
+                resolve_expr(table, arg, emit);
+            }
+        }
+        ExprKind::Unary { operand, .. } => resolve_expr(table, operand, emit),
+        ExprKind::Binary { lhs, rhs, .. } | ExprKind::Logical { lhs, rhs, .. } => {
+            resolve_expr(table, lhs, emit);
+            resolve_expr(table, rhs, emit);
+        }
+        ExprKind::Literal(_) | ExprKind::Var(_) => {}
+    }
+}
+
+/// Reports `break` and `continue` outside of a loop or exiting more loops than they're nested in
+pub fn check_loop_control(program: &[Item], emit: &mut dyn FnMut(Simple<Token, Span>)) {
+    for item in program {
+        match item {
+            // A function body is never inside a loop, even when it's called from one
+            Item::FuncDef(func) => check_body(&func.body, 0, emit),
+            Item::Stmt(stmt) => check_stmt(stmt, 0, emit),
+        }
+    }
+}
+
+fn check_body(body: &[Stmt], loops: usize, emit: &mut dyn FnMut(Simple<Token, Span>)) {
+    for stmt in body {
+        check_stmt(stmt, loops, emit);
+    }
+}
+
+fn check_stmt(stmt: &Stmt, loops: usize, emit: &mut dyn FnMut(Simple<Token, Span>)) {
+    match stmt {
+        Stmt::If(If {
+            if_body, else_body, ..
+        }) => {
+            check_body(if_body, loops, emit);
+            check_body(else_body, loops, emit);
+        }
+        Stmt::While(While { body, .. }) | Stmt::For(For { body, .. }) => {
+            check_body(body, loops + 1, emit)
+        }
+        Stmt::Block { body, .. } => check_body(body, loops, emit),
+        Stmt::Break { span, depth } => check_depth("break", span, *depth, loops, emit),
+        Stmt::Continue { span, depth } => check_depth("continue", span, *depth, loops, emit),
+        Stmt::Exp { .. }
+        | Stmt::VarAssignment { .. }
+        | Stmt::Return { .. }
+        | Stmt::Error { .. } => {}
+    }
+}
+
+fn check_depth(
+    keyword: &str,
+    span: &Span,
+    depth: usize,
+    loops: usize,
+    emit: &mut dyn FnMut(Simple<Token, Span>),
+) {
+    if loops == 0 {
+        emit(error!(
+            span.clone(),
+            format!("`{keyword}` outside of a loop")
+        ));
+    } else if depth > loops {
+        emit(error!(
+            span.clone(),
+            format!("`{keyword} {depth}` can't exit {depth} loops, it's nested in only {loops}")
+        ));
+    }
+}
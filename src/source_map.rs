@@ -0,0 +1,196 @@
+use std::fs;
+use std::io;
+use std::ops::{Index, Range};
+use std::path::Path;
+
+use chumsky::Stream;
+
+use crate::Span;
+
+/// Identifies a file loaded into a [`SourceMap`]
+#[derive(Debug, Default, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct FileId(u32);
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    id: FileId,
+    name: String,
+    src: String,
+    /// Invalid UTF-8 the file was read with, the bytes were replaced one for one by `?`
+    invalid_utf8: Vec<Span>,
+    /// Byte offset of the start of every line
+    line_starts: Vec<usize>,
+    /// Char index of the start of every line
+    line_char_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(id: FileId, name: String, src: String, invalid_utf8: Vec<Span>) -> Self {
+        let mut line_starts = vec![0];
+        let mut line_char_starts = vec![0];
+
+        for (chars, (byte, c)) in src.char_indices().enumerate() {
+            if c == '\n' {
+                line_starts.push(byte + 1);
+                line_char_starts.push(chars + 1);
+            }
+        }
+
+        Self {
+            id,
+            name,
+            src,
+            invalid_utf8,
+            line_starts,
+            line_char_starts,
+        }
+    }
+
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    /// Spans of the invalid UTF-8 sequences the file contained
+    pub fn invalid_utf8(&self) -> &[Span] {
+        &self.invalid_utf8
+    }
+
+    pub fn span(&self, range: Range<usize>) -> Span {
+        Span::new(self.id, range)
+    }
+
+    /// The empty span at the end of the file
+    pub fn eoi(&self) -> Span {
+        self.span(self.src.len()..self.src.len())
+    }
+
+    /// The source text a span points at
+    pub fn slice(&self, span: &Span) -> &str {
+        &self.src[span.range()]
+    }
+
+    /// The line a byte offset is on, lines are counted from 0
+    pub fn line(&self, byte: usize) -> usize {
+        self.line_starts.partition_point(|start| *start <= byte) - 1
+    }
+
+    /// Line and column of a byte offset, both counted from 0, columns count characters
+    pub fn line_col(&self, byte: usize) -> (usize, usize) {
+        let line = self.line(byte);
+        let column = self.src[self.line_starts[line]..byte].chars().count();
+
+        (line, column)
+    }
+
+    pub fn byte_to_char(&self, byte: usize) -> usize {
+        let (line, column) = self.line_col(byte);
+
+        self.line_char_starts[line] + column
+    }
+
+    pub fn char_to_byte(&self, char: usize) -> usize {
+        let line = self
+            .line_char_starts
+            .partition_point(|start| *start <= char)
+            - 1;
+        let start = self.line_starts[line];
+
+        self.src[start..]
+            .char_indices()
+            .nth(char - self.line_char_starts[line])
+            .map_or(self.src.len(), |(byte, _)| start + byte)
+    }
+
+    /// The characters of the file with their spans, ready to be lexed.
+    ///
+    /// A `\r\n` line ending is a single `\n` spanning both bytes.
+    pub fn stream(&self) -> Stream<'_, char, Span, impl Iterator<Item = (char, Span)> + '_> {
+        let mut chars = self.src.char_indices().peekable();
+        let chars = std::iter::from_fn(move || {
+            let (byte, c) = chars.next()?;
+
+            if c == '\r' && chars.next_if(|(_, c)| *c == '\n').is_some() {
+                return Some(('\n', self.span(byte..byte + 2)));
+            }
+
+            Some((c, self.span(byte..byte + c.len_utf8())))
+        });
+
+        Stream::from_iter(self.eoi(), chars)
+    }
+}
+
+/// Owns all the loaded source files
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, src: impl Into<String>) -> FileId {
+        self.push(name.into(), src.into(), Vec::new())
+    }
+
+    /// Adds a file that doesn't have to be valid UTF-8, see [`SourceFile::invalid_utf8`]
+    pub fn add_bytes(&mut self, name: impl Into<String>, mut bytes: Vec<u8>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        let mut invalid_utf8 = Vec::new();
+
+        let mut start = 0;
+        while let Err(err) = std::str::from_utf8(&bytes[start..]) {
+            let invalid = start + err.valid_up_to();
+            let len = err.error_len().unwrap_or(bytes.len() - invalid);
+
+            bytes[invalid..invalid + len].fill(b'?');
+            invalid_utf8.push(Span::new(id, invalid..invalid + len));
+            start = invalid + len;
+        }
+
+        // Every invalid sequence was replaced
+        let src = String::from_utf8(bytes).unwrap_or_default();
+        self.push(name.into(), src, invalid_utf8)
+    }
+
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<FileId> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+
+        Ok(self.add_bytes(path.display().to_string(), bytes))
+    }
+
+    fn push(&mut self, name: String, src: String, invalid_utf8: Vec<Span>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files
+            .push(SourceFile::new(id, name, src, invalid_utf8));
+
+        id
+    }
+
+    pub fn get(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get(id.0 as usize)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+}
+
+impl Index<FileId> for SourceMap {
+    type Output = SourceFile;
+
+    fn index(&self, id: FileId) -> &Self::Output {
+        &self.files[id.0 as usize]
+    }
+}
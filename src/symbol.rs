@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::{Mutex, OnceLock};
+
+/// An interned identifier, comparing symbols compares only their indices.
+///
+/// Only names are interned, string literals aren't.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Symbol(u32);
+
+/// Strings of the first bucket, every next bucket is twice as large
+const FIRST_BUCKET: usize = 32;
+/// Enough buckets for every `u32` index
+const BUCKETS: usize = 28;
+
+/// Interned strings are leaked, they live as long as the compiler does.
+///
+/// The strings are appended to buckets that never move, so they can be read without locking.
+struct Strings {
+    buckets: [OnceLock<Box<[OnceLock<&'static str>]>>; BUCKETS],
+}
+
+impl Strings {
+    /// The bucket and the position in it of the string with the given index
+    fn locate(index: u32) -> (usize, usize) {
+        let index = index as usize + FIRST_BUCKET;
+        let bucket = (index.ilog2() - FIRST_BUCKET.ilog2()) as usize;
+
+        (bucket, index - (FIRST_BUCKET << bucket))
+    }
+
+    fn get(&self, index: u32) -> Option<&'static str> {
+        let (bucket, slot) = Self::locate(index);
+
+        self.buckets[bucket].get()?[slot].get().copied()
+    }
+
+    /// Only the interner calls it, with every index exactly once and in order
+    fn set(&self, index: u32, string: &'static str) {
+        let (bucket, slot) = Self::locate(index);
+        let bucket = self.buckets[bucket].get_or_init(|| {
+            (0..FIRST_BUCKET << bucket)
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+
+        let _ = bucket[slot].set(string);
+    }
+}
+
+fn strings() -> &'static Strings {
+    static STRINGS: OnceLock<Strings> = OnceLock::new();
+
+    STRINGS.get_or_init(|| Strings {
+        buckets: std::array::from_fn(|_| OnceLock::new()),
+    })
+}
+
+fn interner() -> &'static Mutex<HashMap<&'static str, Symbol>> {
+    static INTERNER: OnceLock<Mutex<HashMap<&'static str, Symbol>>> = OnceLock::new();
+
+    INTERNER.get_or_init(Default::default)
+}
+
+impl Symbol {
+    /// Allocates only the first time a string is interned
+    pub fn intern(string: &str) -> Self {
+        Self::intern_owned(string)
+    }
+
+    /// An owned string is leaked as is instead of being copied
+    fn intern_owned(string: impl AsRef<str> + Into<Box<str>>) -> Self {
+        let mut symbols = interner().lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(symbol) = symbols.get(string.as_ref()) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(symbols.len() as u32);
+        let string: &'static str = Box::leak(string.into());
+
+        strings().set(symbol.0, string);
+        symbols.insert(string, symbol);
+
+        symbol
+    }
+
+    /// Doesn't lock the interner, interned strings never move
+    pub fn as_str(self) -> &'static str {
+        // A symbol is only handed out after its string was stored
+        strings().get(self.0).unwrap_or_default()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Self {
+        Symbol::intern(string)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(string: String) -> Self {
+        Symbol::intern_owned(string)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Printing the index alone would make the debug output useless
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
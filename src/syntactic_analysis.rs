@@ -1,3 +1,7 @@
 pub mod ast;
+mod extension;
 mod helpers;
 mod parser;
+
+pub use extension::{Extension, Extensions};
+pub use parser::parser;
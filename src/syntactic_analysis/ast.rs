@@ -1,78 +1,261 @@
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 
-use crate::Span;
-
-#[derive(Debug)]
-pub enum MainBody {
-    FuncDef {
-        func_ident: String,
-        args: Vec<Param>,
-        return_ty: Type,
-        body: Vec<Body>,
-    },
+use crate::token::StrId;
+use crate::{Span, Symbol, WithSpan};
+
+/// A top level item of a program
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    FuncDef(FuncDef),
+    Stmt(Stmt),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuncDef {
+    pub span: Span,
+    pub func_ident: WithSpan<Symbol>,
+    pub args: Vec<Param>,
+    pub return_ty: WithSpan<Type>,
+    pub body: Vec<Stmt>,
+}
 
+/// A statement, the same at the top level and inside a function
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
     If(If),
 
     While(While),
 
-    Exp(RVal),
+    For(For),
 
-    VarAssignment {
-        ident: String,
-        expression: RVal,
+    /// A bare `{ ... }` block
+    Block {
+        span: Span,
+        body: Vec<Stmt>,
     },
 
-    Return,
-    Error,
-}
+    Exp {
+        span: Span,
+        expr: Expr,
+    },
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Body {
-    If {},
+    VarAssignment {
+        span: Span,
+        ident: Symbol,
+        expression: Expr,
+    },
 
-    While {
+    Return {
         span: Span,
-        expr: RVal,
-        body: Vec<Body>,
+        expr: Option<Expr>,
     },
 
-    Exp(RVal),
+    /// `break 2;` exits two loops, the depth is 1 when omitted
+    Break {
+        span: Span,
+        depth: usize,
+    },
 
-    VarAssignment {
-        ident: String,
-        expression: RVal,
+    Continue {
+        span: Span,
+        depth: usize,
+    },
+
+    Error {
+        span: Span,
     },
+}
 
-    Return(RVal),
-    Error,
+impl Stmt {
+    pub fn span(&self) -> &Span {
+        match self {
+            Stmt::If(If { span, .. })
+            | Stmt::While(While { span, .. })
+            | Stmt::For(For { span, .. })
+            | Stmt::Block { span, .. }
+            | Stmt::Exp { span, .. }
+            | Stmt::VarAssignment { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::Break { span, .. }
+            | Stmt::Continue { span, .. }
+            | Stmt::Error { span } => span,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct If {
-    pub(crate) expr: RVal,
-    pub(crate) if_body: Vec<Body>,
-    pub(crate) else_body: Vec<Body>,
+    pub span: Span,
+    pub expr: Expr,
+    pub if_body: Vec<Stmt>,
+    /// Empty without an `else`, an `elseif` is an `If` nested inside
+    pub else_body: Vec<Stmt>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct While {
-    pub expr: RVal,
-    pub body: Vec<Body>,
+    pub span: Span,
+    pub expr: Expr,
+    pub body: Vec<Stmt>,
+}
+
+/// `for (init; cond; step) { body }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct For {
+    pub span: Span,
+    /// Assignments and expressions separated by commas
+    pub init: Vec<Stmt>,
+    /// Loops forever when missing
+    pub cond: Option<Expr>,
+    pub step: Vec<Stmt>,
+    pub body: Vec<Stmt>,
+}
+
+/// An expression with the span of the source it was parsed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub span: Span,
+    pub kind: ExprKind,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum RVal {
-    FunctionCall {
-        ident: String,
-        args: Vec<Vec<Expression>>,
+pub enum ExprKind {
+    Literal(Literal),
+
+    Var(Symbol),
+
+    Call {
+        ident: Symbol,
+        args: Vec<Expr>,
+    },
+
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
     },
 
-    Expr {
-        expr: Vec<Expression>,
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+
+    /// A short circuiting operator, `rhs` is evaluated only when `lhs` doesn't decide the result
+    Logical {
+        op: LogicalOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
     },
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(StrId),
+    Null,
+}
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum UnaryOp {
+    Not,
+    Negate,
+}
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Concat,
+    Smaller,
+    Greater,
+    Equals,
+    NotEquals,
+    SmallerEquals,
+    GreaterEquals,
+}
+
+/// `&&` and `and` are both [`LogicalOp::And`], they differ only in precedence
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum LogicalOp {
+    And,
+    Or,
+}
+
+impl Expr {
+    pub fn new(span: Span, kind: ExprKind) -> Self {
+        Self { span, kind }
+    }
+
+    /// Lowers the tree into reverse Polish notation for the stack based code generator
+    pub fn to_reverse_polish(&self) -> Vec<Expression> {
+        let mut rpn = Vec::new();
+        self.push_reverse_polish(&mut rpn);
+
+        rpn
+    }
+
+    fn push_reverse_polish(&self, rpn: &mut Vec<Expression>) {
+        match &self.kind {
+            ExprKind::Literal(literal) => rpn.push(Expression::Term(literal.clone().into())),
+            ExprKind::Var(name) => rpn.push(Expression::Term(Term::Var(*name))),
+            ExprKind::Call { ident, args } => {
+                for arg in args {
+                    arg.push_reverse_polish(rpn);
+                }
+
+                rpn.push(Expression::Call {
+                    ident: *ident,
+                    args: args.len(),
+                });
+            }
+            ExprKind::Unary { op, operand } => {
+                operand.push_reverse_polish(rpn);
+
+                rpn.push(match op {
+                    UnaryOp::Not => Expression::Not,
+                    UnaryOp::Negate => Expression::Negate,
+                });
+            }
+            ExprKind::Logical { op, lhs, rhs } => {
+                lhs.push_reverse_polish(rpn);
+
+                let rhs = rhs.to_reverse_polish();
+                let skip = rhs.len();
+
+                rpn.push(match op {
+                    LogicalOp::And => Expression::And { skip },
+                    LogicalOp::Or => Expression::Or { skip },
+                });
+                rpn.extend(rhs);
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                lhs.push_reverse_polish(rpn);
+                rhs.push_reverse_polish(rpn);
+
+                rpn.push(match op {
+                    BinaryOp::Add => Expression::Add,
+                    BinaryOp::Subtract => Expression::Subtract,
+                    BinaryOp::Multiply => Expression::Multiply,
+                    BinaryOp::Divide => Expression::Divide,
+                    BinaryOp::Concat => Expression::Concat,
+                    BinaryOp::Smaller => Expression::Smaller,
+                    BinaryOp::Greater => Expression::Greater,
+                    BinaryOp::Equals => Expression::Equals,
+                    BinaryOp::NotEquals => Expression::NotEquals,
+                    BinaryOp::SmallerEquals => Expression::SmallerEquals,
+                    BinaryOp::GreaterEquals => Expression::GreaterEquals,
+                });
+            }
+        }
+    }
+}
+
+/// An instruction of an expression lowered into reverse Polish notation
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Add,
@@ -87,6 +270,20 @@ pub enum Expression {
     SmallerEquals,
     GreaterEquals,
     Not,
+    Negate,
+    /// Pops the left operand, if it's falsy pushes `false` and skips the `skip` instructions of the right operand
+    And {
+        skip: usize,
+    },
+    /// Pops the left operand, if it's truthy pushes `true` and skips the `skip` instructions of the right operand
+    Or {
+        skip: usize,
+    },
+    /// Calls the function with the given number of arguments from the top of the stack
+    Call {
+        ident: Symbol,
+        args: usize,
+    },
     Term(Term),
 }
 
@@ -95,14 +292,26 @@ pub enum Operation {}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Term {
-    Var(String),
+    Var(Symbol),
     Int(i64), // The original language supported only i32 but why should we limit ourselves to 32 bits
     Float(f64), // Especially when it used a 64 bit floating point type
     Bool(bool),
-    String(String),
+    String(StrId),
     Null,
 }
 
+impl From<Literal> for Term {
+    fn from(literal: Literal) -> Self {
+        match literal {
+            Literal::Int(i) => Term::Int(i),
+            Literal::Float(f) => Term::Float(f),
+            Literal::Bool(b) => Term::Bool(b),
+            Literal::String(s) => Term::String(s),
+            Literal::Null => Term::Null,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 pub enum Type {
     Int { nullable: bool },
@@ -120,6 +329,16 @@ impl Type {
             Type::Void => false,
         }
     }
+
+    /// Returns the nullable variant of the type or `None` if the type can't be nullable
+    pub fn to_nullable(self) -> Option<Self> {
+        match self {
+            Type::Int { .. } => Some(Type::Int { nullable: true }),
+            Type::Float { .. } => Some(Type::Float { nullable: true }),
+            Type::String { .. } => Some(Type::String { nullable: true }),
+            Type::Void => None,
+        }
+    }
 }
 
 impl Display for Type {
@@ -141,6 +360,7 @@ impl Display for Type {
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Param {
-    pub ident: String,
+    pub span: Span,
+    pub ident: Symbol,
     pub r#type: Type,
 }
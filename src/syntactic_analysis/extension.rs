@@ -0,0 +1,58 @@
+use std::fmt::{Display, Formatter};
+
+/// An optional part of the IFJ22 language
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Extension {
+    /// Logical operators, `if` without `else` and `elseif`
+    BoolThen,
+    /// Function calls inside expressions
+    FunExp,
+    /// `for` loops, `break` and `continue`
+    Cycles,
+}
+
+impl Extension {
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl Display for Extension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Extension::BoolThen => "BOOLTHEN",
+            Extension::FunExp => "FUNEXP",
+            Extension::Cycles => "CYCLES",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// The set of extensions the parser accepts, all of them are enabled by default
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct Extensions(u8);
+
+impl Extensions {
+    /// Vanilla IFJ22
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u8::MAX);
+
+    pub const fn with(self, extension: Extension) -> Self {
+        Self(self.0 | extension.bit())
+    }
+
+    pub const fn without(self, extension: Extension) -> Self {
+        Self(self.0 & !extension.bit())
+    }
+
+    pub const fn contains(self, extension: Extension) -> bool {
+        self.0 & extension.bit() != 0
+    }
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
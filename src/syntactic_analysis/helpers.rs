@@ -1,5 +1,9 @@
+use chumsky::prelude::*;
+
+use crate::syntactic_analysis::{Extension, Extensions};
 use crate::token::Token;
 use crate::token::Token::Type;
+use crate::{error, Span};
 
 pub fn expected_types() -> [Option<Token>; 4] {
     use crate::syntactic_analysis::ast::Type::*;
@@ -11,3 +15,38 @@ pub fn expected_types() -> [Option<Token>; 4] {
         Some(Type(Void)),
     ]
 }
+
+/// Reports syntax of a disabled extension, the syntax is still parsed to not cause more errors
+pub fn check_extension(
+    extensions: Extensions,
+    extension: Extension,
+    syntax: &str,
+    span: Span,
+    emit: &mut dyn FnMut(Simple<Token, Span>),
+) {
+    if !extensions.contains(extension) {
+        emit(error!(
+            span,
+            format!("{syntax} is a part of the {extension} extension, which is disabled")
+        ));
+    }
+}
+
+/// Parses a keyword that is a part of an extension
+pub fn extension_keyword(
+    keyword: Token,
+    extension: Extension,
+    extensions: Extensions,
+) -> impl Parser<Token, Span, Error = Simple<Token, Span>> + Clone {
+    just(keyword.clone()).validate(move |_, span: Span, emit| {
+        check_extension(
+            extensions,
+            extension,
+            &format!("`{keyword}`"),
+            span.clone(),
+            emit,
+        );
+
+        span
+    })
+}
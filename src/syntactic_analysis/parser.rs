@@ -4,60 +4,136 @@ mod expression;
 mod f_call;
 mod ident;
 
+use std::collections::HashSet;
+
 use chumsky::prelude::*;
 
-use crate::syntactic_analysis::ast::{Body, MainBody, Type};
+use crate::syntactic_analysis::ast::{FuncDef, Item, Param, Stmt, Type};
 use crate::syntactic_analysis::helpers::expected_types;
+use crate::syntactic_analysis::parser::body::{body, statement};
 use crate::syntactic_analysis::parser::ident::{func_ident, var_ident};
+use crate::syntactic_analysis::Extensions;
 use crate::token::Token;
 use crate::{error, Span, WithSpan};
 
-fn type_parser() -> impl Parser<Token, WithSpan<Type>, Error = Simple<Token>> + Copy {
+fn type_parser() -> impl Parser<Token, WithSpan<Type>, Error = Simple<Token, Span>> + Copy {
     filter_map(|span: Span, token: Token| match token {
-        Token::Ident(ref name) => {
-            let nullable = name.starts_with('?');
-            let r#type = if nullable { name.split_at(1).1 } else { name };
-
-            match r#type {
-                "int" => Ok(WithSpan(span, Type::Int { nullable })),
-                "float" => Ok(WithSpan(span, Type::Float { nullable })),
-                "string" => Ok(WithSpan(span, Type::String { nullable })),
-                "void" if !nullable => Ok(WithSpan(span, Type::Void)),
-                "void" if nullable => Err(error!(span, "a void return value is non nullable")),
-                _ => Err(Simple::expected_input_found(
-                    span,
-                    expected_types(),
-                    Some(token),
-                )),
-            }
-        }
-        _ => Err(Simple::expected_input_found(span, Vec::new(), Some(token))),
+        Token::Type(r#type) => Ok(WithSpan(span, r#type)),
+        _ => Err(Simple::expected_input_found(
+            span,
+            expected_types(),
+            Some(token),
+        )),
     })
 }
 
-pub fn parse_body() -> impl Parser<Token, WithSpan<Vec<WithSpan<Body>>>, Error = Simple<Token>> {
-    let _function_name = func_ident();
-
-    let _var_ident = var_ident();
-
-    todo()
+/// Parses a braces delimited list of statements, like the body of a function or a loop
+pub fn parse_body(
+    extensions: Extensions,
+) -> impl Parser<Token, Vec<Stmt>, Error = Simple<Token, Span>> + Clone {
+    body(extensions).delimited_by(just(Token::Control('{')), just(Token::Control('}')))
 }
 
-pub fn parser() -> impl Parser<Token, MainBody, Error = Simple<Token>> {
-    let _function_name = func_ident();
-
-    let var_ident = var_ident();
+fn function_definition(
+    extensions: Extensions,
+) -> impl Parser<Token, FuncDef, Error = Simple<Token, Span>> + Clone {
+    let arg =
+        type_parser()
+            .then(var_ident())
+            .map_with_span(|(WithSpan(_, r#type), ident), span| Param {
+                span,
+                ident,
+                r#type,
+            });
 
-    let type_parser = type_parser();
-
-    let arg = type_parser.then(var_ident);
-
-    let _args = arg
+    let args = arg
         .separated_by(just(Token::Control(',')))
         .allow_trailing()
         .delimited_by(just(Token::Control('(')), just(Token::Control(')')))
+        .validate(|args: Vec<Param>, _, emit| {
+            let mut declared = HashSet::new();
+
+            for arg in args.iter().filter(|arg| !declared.insert(arg.ident)) {
+                emit(error!(
+                    arg.span.clone(),
+                    format!("parameter `${}` is already declared", arg.ident)
+                ));
+            }
+
+            args
+        })
+        .map_with_span(|args, span| WithSpan(span, args))
         .labelled("function args");
 
-    ();
-    todo()
+    // A missing return type is reported right after the parameters, the function is then treated as void
+    let return_ty = just(Token::Control(':'))
+        .ignore_then(type_parser())
+        .or_not();
+
+    just(Token::Function)
+        .ignore_then(func_ident().map_with_span(|ident, span| WithSpan(span, ident)))
+        .then(args)
+        .then(return_ty)
+        .validate(
+            |((func_ident, WithSpan(args_span, args)), return_ty), _, emit| {
+                let return_ty = return_ty.unwrap_or_else(|| {
+                    let span = args_span.after();
+                    emit(error!(
+                        span.clone(),
+                        format!(
+                            "function `{}` is missing its return type, like `: void`",
+                            *func_ident
+                        )
+                    ));
+
+                    WithSpan(span, Type::Void)
+                });
+
+                ((func_ident, args), return_ty)
+            },
+        )
+        .then(parse_body(extensions))
+        .map_with_span(|(((func_ident, args), return_ty), body), span| FuncDef {
+            span,
+            func_ident,
+            args,
+            return_ty,
+            body,
+        })
+        .labelled("function definition")
+}
+
+/// Parses a function definition or a top level statement
+fn item(
+    extensions: Extensions,
+) -> impl Parser<Token, Option<Item>, Error = Simple<Token, Span>> + Clone {
+    // Recovering from a failed item would swallow the epilogue
+    let item_start = none_of([Token::Epilogue]).rewind();
+
+    let empty = just(Token::Control(';')).to(None);
+
+    let item = choice((
+        function_definition(extensions).map(Item::FuncDef),
+        statement(parse_body(extensions), extensions).map(Item::Stmt),
+    ))
+    .recover_with(
+        skip_until([Token::Control(';'), Token::Control('}')], |span| {
+            Item::Stmt(Stmt::Error { span })
+        })
+        .consume_end(),
+    )
+    .map(Some);
+
+    item_start.ignore_then(empty.or(item))
+}
+
+/// Parses a whole program accepting only the given extensions
+pub fn parser(
+    extensions: Extensions,
+) -> impl Parser<Token, Vec<Item>, Error = Simple<Token, Span>> {
+    just(Token::Prologue)
+        .labelled("prologue")
+        .ignore_then(item(extensions).repeated().flatten())
+        .then_ignore(just(Token::Epilogue).or_not())
+        .then_ignore(end())
 }
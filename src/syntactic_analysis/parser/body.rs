@@ -1,8 +1,105 @@
 use chumsky::prelude::*;
 
-use crate::syntactic_analysis::ast::Body;
-use crate::Token;
+use crate::syntactic_analysis::ast::Stmt;
+use crate::syntactic_analysis::helpers::extension_keyword;
+use crate::syntactic_analysis::parser::control::{for_block, if_block, while_block};
+use crate::syntactic_analysis::parser::ident::{rval, var_ident};
+use crate::syntactic_analysis::{Extension, Extensions};
+use crate::token::Operator;
+use crate::{error, Span, Token, WithSpan};
 
-pub fn body() -> impl Parser<Token, Vec<Body>, Error = Simple<Token>> + Copy {
-    todo()
+/// Parses a single statement, `block` parses a braces delimited list of statements
+pub fn statement(
+    block: impl Parser<Token, Vec<Stmt>, Error = Simple<Token, Span>> + Clone + 'static,
+    extensions: Extensions,
+) -> impl Parser<Token, Stmt, Error = Simple<Token, Span>> + Clone {
+    let control = |c| just(Token::Control(c));
+
+    let assignment = var_ident()
+        .then_ignore(just(Token::Op(Operator::Assign)))
+        .then(rval(extensions))
+        .then_ignore(control(';'))
+        .map_with_span(|(ident, expression), span| Stmt::VarAssignment {
+            span,
+            ident,
+            expression,
+        });
+
+    let ret = just(Token::Return)
+        .ignore_then(rval(extensions).or_not())
+        .then_ignore(control(';'))
+        .map_with_span(|expr, span| Stmt::Return { span, expr });
+
+    // `break 2;` and `continue 2;`, the depth has to be a positive integer literal like in PHP
+    let loop_control = |keyword: Token| {
+        let depth = select! { Token::Int(depth) => depth }
+            .map_with_span(|depth, span| WithSpan(span, depth))
+            .or_not();
+
+        extension_keyword(keyword.clone(), Extension::Cycles, extensions)
+            .ignore_then(depth)
+            .then_ignore(control(';'))
+            .validate(move |depth, _, emit| match depth {
+                Some(WithSpan(span, 0)) => {
+                    emit(error!(
+                        span,
+                        format!("`{keyword} 0` isn't allowed, the depth has to be at least 1")
+                    ));
+                    1
+                }
+                Some(WithSpan(_, depth)) => depth as usize,
+                None => 1,
+            })
+    };
+
+    let r#break =
+        loop_control(Token::Break).map_with_span(|depth, span| Stmt::Break { span, depth });
+
+    let r#continue =
+        loop_control(Token::Continue).map_with_span(|depth, span| Stmt::Continue { span, depth });
+
+    let expr = rval(extensions)
+        .then_ignore(control(';'))
+        .map_with_span(|expr, span| Stmt::Exp { span, expr });
+
+    choice((
+        if_block(block.clone(), extensions).map(Stmt::If),
+        while_block(block.clone(), extensions).map(Stmt::While),
+        for_block(block.clone(), extensions).map(Stmt::For),
+        block.map_with_span(|body, span| Stmt::Block { span, body }),
+        ret,
+        r#break,
+        r#continue,
+        assignment,
+        expr,
+    ))
+}
+
+/// Parses a list of statements up to the closing `}` of the enclosing block
+pub fn body(
+    extensions: Extensions,
+) -> impl Parser<Token, Vec<Stmt>, Error = Simple<Token, Span>> + Clone {
+    recursive(move |body| {
+        let control = |c| just(Token::Control(c));
+
+        let block = body.delimited_by(control('{'), control('}'));
+
+        // An empty statement, also where a broken statement's recovery stops
+        let empty = control(';').to(None);
+
+        // The closing `}` ends the list, recovering from it would consume nothing
+        let statement_start = none_of([Token::Control('}')]).rewind();
+
+        let statement = statement(block, extensions)
+            .recover_with(skip_until(
+                [Token::Control(';'), Token::Control('}')],
+                |span| Stmt::Error { span },
+            ))
+            .map(Some);
+
+        statement_start
+            .ignore_then(empty.or(statement))
+            .repeated()
+            .flatten()
+    })
 }
@@ -1,30 +1,125 @@
 use chumsky::prelude::*;
 
-use crate::syntactic_analysis::ast::{If, While};
-use crate::syntactic_analysis::parser::body::body;
-use crate::syntactic_analysis::parser::ident::rval;
-use crate::Token;
+use crate::syntactic_analysis::ast::{For, If, Stmt, While};
+use crate::syntactic_analysis::helpers::{check_extension, extension_keyword};
+use crate::syntactic_analysis::parser::ident::{rval, var_ident};
+use crate::syntactic_analysis::{Extension, Extensions};
+use crate::token::Operator;
+use crate::{Span, Token, WithSpan};
 
-pub fn if_block() -> impl Parser<Token, If, Error = Simple<Token>> + Clone {
+/// Parses an `if` statement, `elseif` and `else if` branches are nested inside the `else` branch
+pub fn if_block(
+    block: impl Parser<Token, Vec<Stmt>, Error = Simple<Token, Span>> + Clone + 'static,
+    extensions: Extensions,
+) -> impl Parser<Token, If, Error = Simple<Token, Span>> + Clone {
     let control = |c| just(Token::Control(c));
 
+    // Everything after the `if` or `elseif` keyword
+    let branches = recursive(|branches| {
+        // The error names the syntax that was actually written
+        let else_if = just(Token::ElseIf)
+            .to("`elseif`")
+            .or(just(Token::Else).then(just(Token::If)).to("`else if`"))
+            .map_with_span(|syntax, span: Span| (syntax, span))
+            .validate(move |(syntax, span), _, emit| {
+                check_extension(extensions, Extension::BoolThen, syntax, span, emit)
+            })
+            .ignore_then(branches)
+            .map_with_span(|(expr, if_body, else_body), span| {
+                vec![Stmt::If(If {
+                    span,
+                    expr,
+                    if_body,
+                    else_body,
+                })]
+            });
+
+        let r#else = just(Token::Else).ignore_then(block.clone());
+
+        rval(extensions)
+            .delimited_by(control('('), control(')'))
+            .then(
+                block
+                    .clone()
+                    .map_with_span(|body, span| WithSpan(span, body)),
+            )
+            .then(else_if.or(r#else).or_not())
+            .validate(
+                move |((expr, WithSpan(span, if_body)), else_body), _, emit| {
+                    if else_body.is_none() {
+                        let syntax = "an `if` without `else`";
+                        check_extension(
+                            extensions,
+                            Extension::BoolThen,
+                            syntax,
+                            span.after(),
+                            emit,
+                        );
+                    }
+
+                    (expr, if_body, else_body.unwrap_or_default())
+                },
+            )
+    });
+
     just(Token::If)
-        .ignore_then(rval().delimited_by(control('('), control(')')))
-        .then(body().delimited_by(control('{'), control('}')))
-        .then_ignore(just(Token::Else))
-        .then(body().delimited_by(control('{'), control('}')))
-        .map(|((expr, if_body), else_body)| If {
+        .ignore_then(branches)
+        .map_with_span(|(expr, if_body, else_body), span| If {
+            span,
             expr,
             if_body,
             else_body,
         })
 }
 
-pub fn while_block() -> impl Parser<Token, While, Error = Simple<Token>> + Clone {
+pub fn while_block(
+    block: impl Parser<Token, Vec<Stmt>, Error = Simple<Token, Span>> + Clone,
+    extensions: Extensions,
+) -> impl Parser<Token, While, Error = Simple<Token, Span>> + Clone {
     let control = |c| just(Token::Control(c));
 
     just(Token::While)
-        .ignore_then(rval().delimited_by(control('('), control(')')))
-        .then(body().delimited_by(control('{'), control('}')))
-        .map(|(expr, body)| While { expr, body })
+        .ignore_then(rval(extensions).delimited_by(control('('), control(')')))
+        .then(block)
+        .map_with_span(|(expr, body), span| While { span, expr, body })
+}
+
+/// Parses `for (init; cond; step) { }`, `init` and `step` are comma separated assignments or expressions
+pub fn for_block(
+    block: impl Parser<Token, Vec<Stmt>, Error = Simple<Token, Span>> + Clone,
+    extensions: Extensions,
+) -> impl Parser<Token, For, Error = Simple<Token, Span>> + Clone {
+    let control = |c| just(Token::Control(c));
+
+    let assignment = var_ident()
+        .then_ignore(just(Token::Op(Operator::Assign)))
+        .then(rval(extensions))
+        .map_with_span(|(ident, expression), span| Stmt::VarAssignment {
+            span,
+            ident,
+            expression,
+        });
+
+    let expr = rval(extensions).map_with_span(|expr, span| Stmt::Exp { span, expr });
+
+    let list = assignment.or(expr).separated_by(control(','));
+
+    let header = list
+        .clone()
+        .then_ignore(control(';'))
+        .then(rval(extensions).or_not())
+        .then_ignore(control(';'))
+        .then(list)
+        .delimited_by(control('('), control(')'));
+
+    extension_keyword(Token::For, Extension::Cycles, extensions)
+        .ignore_then(header)
+        .then(block)
+        .map_with_span(|(((init, cond), step), body), span| For {
+            span,
+            init,
+            cond,
+            step,
+            body,
+        })
 }
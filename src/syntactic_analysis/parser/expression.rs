@@ -1,190 +1,268 @@
-use std::collections::VecDeque;
-use std::str::FromStr;
+use std::iter::Peekable;
 
 use chumsky::prelude::*;
 
-use crate::syntactic_analysis::ast::{Expression, Term};
-use crate::{error, Span, Token};
-
-pub enum Expr {
-    Add(Box<Expr>, Box<Expr>),
-    Sub(Box<Expr>, Box<Expr>),
-    Mul(Box<Expr>, Box<Expr>),
-    Div(Box<Expr>, Box<Expr>),
-    Concat(Box<Expr>, Box<Expr>),
-    Less(Box<Expr>, Box<Expr>),
-    More(Box<Expr>, Box<Expr>),
-    LessEq(Box<Expr>, Box<Expr>),
-    MoreEq(Box<Expr>, Box<Expr>),
-    Eq(Box<Expr>, Box<Expr>),
-    NEq(Box<Expr>, Box<Expr>),
-    Var(String),
-    Int(i64),
-    Float(f64),
-    String(String),
-    Bool(bool),
-    Null,
+use crate::syntactic_analysis::ast::{BinaryOp, Expr, ExprKind, Literal, LogicalOp, UnaryOp};
+use crate::syntactic_analysis::helpers::check_extension;
+use crate::syntactic_analysis::parser::f_call::function_call;
+use crate::syntactic_analysis::{Extension, Extensions};
+use crate::token::{Operator, StrId, StrPart};
+use crate::{error, Span, Token, WithSpan};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Associativity {
+    Left,
+    /// Chaining the operator like `$a < $b < $c` is an error
+    Non,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Infix {
+    Binary(BinaryOp),
+    Logical(LogicalOp),
 }
 
-fn parse_num(span: Span, num: String) -> Result<Expr, Simple<Token>> {
-    if num.contains(['e', 'E', '.']) {
-        f64::from_str(&num)
-            .map(Expr::Float)
-            .map_err(|_| error!(span, format!("Failed to parse {num} as float")))
-    } else {
-        i64::from_str(&num)
-            .map(Expr::Int)
-            .map_err(|_| error!(span, format!("Failed to parse {num} as int")))
+impl Infix {
+    fn build(self, lhs: Expr, rhs: Expr) -> Expr {
+        let span = lhs.span.join(&rhs.span);
+        let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+
+        let kind = match self {
+            Infix::Binary(op) => ExprKind::Binary { op, lhs, rhs },
+            Infix::Logical(op) => ExprKind::Logical { op, lhs, rhs },
+        };
+
+        Expr::new(span, kind)
     }
 }
 
-pub fn expression() -> impl Parser<Token, Expr, Error = Simple<Token>> + Clone {
-    recursive(|expr| {
-        let num = filter_map(|span, token| match token {
-            Token::Num(n) => parse_num(span, n),
-            n => Err(Simple::expected_input_found(span, [], Some(n))),
-        });
+#[derive(Debug)]
+struct InfixOperator {
+    token: Operator,
+    infix: Infix,
+    /// Operators with a higher precedence bind tighter
+    precedence: u8,
+    associativity: Associativity,
+    /// The extension the operator is a part of, `None` for vanilla IFJ22 operators
+    extension: Option<Extension>,
+}
 
-        let term = select! {
-        Token::Null => Expr::Null,
-        Token::Bool(b) => Expr::Bool(b),
-        Token::Str(s) => Expr::String(s),
-        Token::Ident(i) if i.starts_with('$') => Expr::Var(i),
+impl InfixOperator {
+    const fn new(
+        token: Operator,
+        infix: Infix,
+        precedence: u8,
+        associativity: Associativity,
+    ) -> Self {
+        Self {
+            token,
+            infix,
+            precedence,
+            associativity,
+            extension: None,
         }
-        .or(num);
+    }
 
-        let cont = |c| just(Token::Control(c));
+    const fn extension(self, extension: Extension) -> Self {
+        Self {
+            extension: Some(extension),
+            ..self
+        }
+    }
+}
 
-        let atom = term.or(expr.delimited_by(cont('('), cont(')')));
+/// Binary operators following PHP's precedence table
+const INFIX_OPERATORS: [InfixOperator; 15] = {
+    use Associativity::*;
+    use Extension::*;
+    use Infix::*;
 
-        let op = |c: &str| just(Token::Op(c.to_string()));
+    [
+        InfixOperator::new(Operator::Multiply, Binary(BinaryOp::Multiply), 9, Left),
+        InfixOperator::new(Operator::Divide, Binary(BinaryOp::Divide), 9, Left),
+        InfixOperator::new(Operator::Add, Binary(BinaryOp::Add), 8, Left),
+        InfixOperator::new(Operator::Subtract, Binary(BinaryOp::Subtract), 8, Left),
+        // Since PHP 8 `.` binds looser than `+` and `-`
+        InfixOperator::new(Operator::Concat, Binary(BinaryOp::Concat), 7, Left),
+        InfixOperator::new(Operator::Less, Binary(BinaryOp::Smaller), 6, Non),
+        InfixOperator::new(Operator::LessEq, Binary(BinaryOp::SmallerEquals), 6, Non),
+        InfixOperator::new(Operator::Greater, Binary(BinaryOp::Greater), 6, Non),
+        InfixOperator::new(Operator::GreaterEq, Binary(BinaryOp::GreaterEquals), 6, Non),
+        InfixOperator::new(Operator::Equals, Binary(BinaryOp::Equals), 5, Non),
+        InfixOperator::new(Operator::NotEquals, Binary(BinaryOp::NotEquals), 5, Non),
+        InfixOperator::new(Operator::And, Logical(LogicalOp::And), 4, Left).extension(BoolThen),
+        InfixOperator::new(Operator::Or, Logical(LogicalOp::Or), 3, Left).extension(BoolThen),
+        // The keywords bind looser than anything else, even `||`
+        InfixOperator::new(Operator::AndKeyword, Logical(LogicalOp::And), 2, Left)
+            .extension(BoolThen),
+        InfixOperator::new(Operator::OrKeyword, Logical(LogicalOp::Or), 1, Left)
+            .extension(BoolThen),
+    ]
+};
 
-        // First in precedence table
-        let product = atom
-            .clone()
-            .then(
-                op("*")
-                    .to(Expr::Mul as fn(_, _) -> _)
-                    .or(op("/").to(Expr::Div as fn(_, _) -> _))
-                    .then(atom)
-                    .repeated(),
-            )
-            .foldl(|lhs, (op, rhs)| op(Box::new(lhs), Box::new(rhs)));
-
-        // Second in precedence table
-        let sum = product
-            .clone()
-            .then(
-                op("+")
-                    .to(Expr::Add as fn(_, _) -> _)
-                    .or(op("-").to(Expr::Sub as fn(_, _) -> _))
-                    .or(op(".").to(Expr::Concat as fn(_, _) -> _))
-                    .then(product)
-                    .repeated(),
-            )
-            .foldl(|lhs, (op, rhs)| op(Box::new(lhs), Box::new(rhs)));
-
-        let cmp = sum
-            .clone()
-            .then(
-                op("<")
-                    .to(Expr::Less as fn(_, _) -> _)
-                    .or(op(">").to(Expr::More as fn(_, _) -> _))
-                    .or(op("<=").to(Expr::LessEq as fn(_, _) -> _))
-                    .or(op(">=").to(Expr::MoreEq as fn(_, _) -> _))
-                    .then(sum)
-                    .repeated(),
-            )
-            .foldl(|lhs, (op, rhs)| op(Box::new(lhs), Box::new(rhs)));
-
-        let eq = cmp
-            .clone()
-            .then(
-                op("===")
-                    .to(Expr::Eq as fn(_, _) -> _)
-                    .or(op("==!").to(Expr::NEq as fn(_, _) -> _))
-                    .then(cmp)
-                    .repeated(),
-            )
-            .foldl(|lhs, (op, rhs)| op(Box::new(lhs), Box::new(rhs)));
-
-        eq
+/// Prefix operators bind tighter than any infix one, `-$a * 2` is `(-$a) * 2`
+const PREFIX_OPERATORS: [(Operator, UnaryOp, Option<Extension>); 2] = [
+    (Operator::Not, UnaryOp::Not, Some(Extension::BoolThen)),
+    (Operator::Subtract, UnaryOp::Negate, None),
+];
+
+/// Turns `"Hello $name\n"` into `"Hello " . $name . "\n"`, the literal pieces get the string's span
+fn desugar_interpolation(parts: Vec<StrPart>, span: Span) -> Expr {
+    let string = |lit| Expr::new(span.clone(), ExprKind::Literal(Literal::String(lit)));
+    let concat = |lhs, rhs| Infix::Binary(BinaryOp::Concat).build(lhs, rhs);
+
+    let mut parts = parts.into_iter().map(|part| match part {
+        StrPart::Lit(lit) => string(lit),
+        StrPart::Var(WithSpan(span, name)) => Expr::new(span, ExprKind::Var(name)),
+    });
+
+    // Concatenating with an empty string makes sure `"$a"` is still a string
+    let first = match parts.next() {
+        Some(
+            var @ Expr {
+                kind: ExprKind::Var(_),
+                ..
+            },
+        ) => concat(string(StrId::EMPTY), var),
+        Some(first) => first,
+        None => string(StrId::EMPTY),
+    };
+
+    // The whole expression spans the whole string, quotes included
+    let expr = parts.fold(first, concat);
+    Expr::new(span, expr.kind)
+}
+
+fn prefix_operator(
+    extensions: Extensions,
+) -> impl Parser<Token, (UnaryOp, Span), Error = Simple<Token, Span>> + Clone {
+    filter_map(|span: Span, token| {
+        PREFIX_OPERATORS
+            .iter()
+            .find(|(op, ..)| Token::Op(*op) == token)
+            .copied()
+            .ok_or_else(|| Simple::expected_input_found(span, Vec::new(), Some(token)))
+    })
+    .validate(move |(op, unary, extension), span: Span, emit| {
+        if let Some(extension) = extension {
+            check_extension(
+                extensions,
+                extension,
+                &format!("`{op}`"),
+                span.clone(),
+                emit,
+            );
+        }
+
+        (unary, span)
     })
 }
 
-pub fn expression_to_reverse_polish(expr: Expr) -> Vec<Expression> {
-    let t = Expression::Term;
+fn infix_operator(
+    extensions: Extensions,
+) -> impl Parser<Token, (&'static InfixOperator, Span), Error = Simple<Token, Span>> + Clone {
+    filter_map(|span: Span, token| {
+        INFIX_OPERATORS
+            .iter()
+            .find(|op| Token::Op(op.token) == token)
+            .ok_or_else(|| Simple::expected_input_found(span, Vec::new(), Some(token)))
+    })
+    .validate(move |op, span: Span, emit| {
+        if let Some(extension) = op.extension {
+            check_extension(
+                extensions,
+                extension,
+                &format!("`{}`", op.token),
+                span.clone(),
+                emit,
+            );
+        }
 
-    let mut result = VecDeque::new();
-    let mut stack = vec![expr];
+        (op, span)
+    })
+}
 
-    while !stack.is_empty() {
-        let top = stack.pop().unwrap(); // Stack shouldn't be empty at this point
+/// Precedence climbing, folds the operators binding at least as tight as `min_precedence` into `lhs`
+fn climb(
+    mut lhs: Expr,
+    min_precedence: u8,
+    rest: &mut Peekable<impl Iterator<Item = ((&'static InfixOperator, Span), Expr)>>,
+    emit: &mut dyn FnMut(Simple<Token, Span>),
+) -> Expr {
+    while let Some(((op, _), rhs)) = rest.next_if(|((op, _), _)| op.precedence >= min_precedence) {
+        // Only operators binding tighter belong to the right operand, which makes `op` left associative
+        let rhs = climb(rhs, op.precedence + 1, rest, emit);
 
-        match top {
-            Expr::Add(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Add);
-            }
-            Expr::Sub(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Subtract);
-            }
-            Expr::Mul(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Multiply);
-            }
-            Expr::Div(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Divide);
-            }
-            Expr::Concat(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Concat);
-            }
-            Expr::Less(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Smaller);
+        if op.associativity == Associativity::Non {
+            if let Some(((next, span), _)) = rest
+                .peek()
+                .filter(|((next, _), _)| next.precedence == op.precedence)
+            {
+                emit(error!(
+                    span.clone(),
+                    format!(
+                        "`{}` can't be chained with `{}` without parentheses, the operators are non associative",
+                        next.token, op.token
+                    )
+                ));
             }
-            Expr::More(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Greater);
-            }
-            Expr::LessEq(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::SmallerEquals);
-            }
-            Expr::MoreEq(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::GreaterEquals);
-            }
-            Expr::Eq(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::Equals);
-            }
-            Expr::NEq(a, b) => {
-                stack.push(*a);
-                stack.push(*b);
-                result.push_front(Expression::NotEquals);
-            }
-            Expr::Var(ident) => result.push_front(t(Term::Var(ident))),
-            Expr::Int(i) => result.push_front(t(Term::Int(i))),
-            Expr::Float(f) => result.push_front(t(Term::Float(f))),
-            Expr::String(s) => result.push_front(t(Term::String(s))),
-            Expr::Bool(b) => result.push_front(t(Term::Bool(b))),
-            Expr::Null => result.push_front(t(Term::Null)),
         }
+
+        lhs = op.infix.build(lhs, rhs);
     }
 
-    result.into()
+    lhs
+}
+
+pub fn expression(
+    extensions: Extensions,
+) -> impl Parser<Token, Expr, Error = Simple<Token, Span>> + Clone {
+    recursive(move |expr| {
+        let term = select! {
+            Token::Null => ExprKind::Literal(Literal::Null),
+            Token::Bool(b) => ExprKind::Literal(Literal::Bool(b)),
+            Token::Int(i) => ExprKind::Literal(Literal::Int(i)),
+            Token::Float(f) => ExprKind::Literal(Literal::Float(f.0)),
+            Token::Str(s) => ExprKind::Literal(Literal::String(s)),
+            Token::Var(name) => ExprKind::Var(name),
+        }
+        .map_with_span(|kind, span| Expr::new(span, kind));
+
+        let interpolated =
+            select! { Token::InterpolatedStr(parts) => parts }.map_with_span(desugar_interpolation);
+
+        let cont = |c| just(Token::Control(c));
+
+        // A parenthesized expression spans its parentheses too
+        let parenthesized = expr
+            .clone()
+            .delimited_by(cont('('), cont(')'))
+            .map_with_span(|expr: Expr, span| Expr::new(span, expr.kind));
+
+        let atom = function_call(expr)
+            .or(term)
+            .or(interpolated)
+            .or(parenthesized);
+
+        let unary =
+            prefix_operator(extensions)
+                .repeated()
+                .then(atom)
+                .foldr(|(op, span), operand| {
+                    Expr::new(
+                        span.join(&operand.span),
+                        ExprKind::Unary {
+                            op,
+                            operand: Box::new(operand),
+                        },
+                    )
+                });
+
+        unary
+            .clone()
+            .then(infix_operator(extensions).then(unary).repeated())
+            .validate(|(first, rest), _, emit| {
+                climb(first, 0, &mut rest.into_iter().peekable(), emit)
+            })
+    })
 }
@@ -1,20 +1,20 @@
 use chumsky::prelude::*;
 
-use crate::syntactic_analysis::ast::RVal;
-use crate::syntactic_analysis::parser::expression::{expression, expression_to_reverse_polish};
+use crate::syntactic_analysis::ast::{Expr, ExprKind};
 use crate::syntactic_analysis::parser::ident::func_ident;
-use crate::Token;
+use crate::{Span, Token};
 
-pub fn function_call() -> impl Parser<Token, RVal, Error = Simple<Token>> + Clone {
+/// Parses a call, `expr` parses the arguments
+pub fn function_call(
+    expr: impl Parser<Token, Expr, Error = Simple<Token, Span>> + Clone,
+) -> impl Parser<Token, Expr, Error = Simple<Token, Span>> + Clone {
     let control = |c| just(Token::Control(c));
 
     func_ident()
         .then(
-            expression()
-                .map(expression_to_reverse_polish)
-                .separated_by(control(','))
+            expr.separated_by(control(','))
                 .allow_trailing()
                 .delimited_by(control('('), control(')')),
         )
-        .map(|(ident, args)| RVal::FunctionCall { ident, args })
+        .map_with_span(|(ident, args), span| Expr::new(span, ExprKind::Call { ident, args }))
 }
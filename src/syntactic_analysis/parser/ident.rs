@@ -1,61 +1,58 @@
 use chumsky::prelude::*;
 
-use crate::syntactic_analysis::ast::RVal;
-use crate::syntactic_analysis::parser::expression::{expression, expression_to_reverse_polish};
-use crate::syntactic_analysis::parser::f_call::function_call;
-use crate::{error, unreachable, Span, Token};
-
-fn check_ident(span: Span, ident: String) -> Result<String, Simple<Token>> {
-    let mut chars = ident.chars();
-    if let Some(first_char) = chars.next() {
-        if !first_char.is_alphanumeric() {
-            return Err(error!(
-                span,
-                "identifiers are meant to start with an alphanumeric symbol"
-            ));
-        }
-    } else {
-        return Err(unreachable!(span, "encountered an empty identifier")); // An empty identifier shouldn't be constructable
-    }
-
-    for c in chars {
-        if !(c.is_alphanumeric() || c == '_') {
-            return Err(error!(
-                span,
-                "identifiers are meant to be composed of alphanumeric symbols and the _ symbol"
-            ));
-        }
-    }
+use crate::syntactic_analysis::ast::{Expr, ExprKind};
+use crate::syntactic_analysis::parser::expression::expression;
+use crate::syntactic_analysis::{Extension, Extensions};
+use crate::{error, Span, Symbol, Token};
 
-    Ok(ident)
-}
-
-pub fn func_ident() -> impl Parser<Token, String, Error = Simple<Token>> + Copy {
+pub fn func_ident() -> impl Parser<Token, Symbol, Error = Simple<Token, Span>> + Copy {
     filter_map(|span, token| match token {
-        Token::Ident(name) => check_ident(span, name),
+        Token::Ident(name) => Ok(name),
         _ => Err(Simple::expected_input_found(span, Vec::new(), Some(token))),
     })
 }
 
-pub fn var_ident() -> impl Parser<Token, String, Error = Simple<Token>> + Copy {
+pub fn var_ident() -> impl Parser<Token, Symbol, Error = Simple<Token, Span>> + Copy {
     filter_map(|span, token| match token {
-        Token::Ident(name) => {
-            let (start, rest) = name.split_at(1);
-            if start != "$" {
-                return Err(error!(span, "variable identifiers should start with $"));
-            }
+        Token::Var(name) => Ok(name),
+        _ => Err(Simple::expected_input_found(span, Vec::new(), Some(token))),
+    })
+}
 
-            check_ident(span, rest.to_string())
+/// Reports every call inside `expr`, vanilla IFJ22 allows a call only as a whole right hand side
+fn report_nested_calls(expr: &Expr, emit: &mut dyn FnMut(Simple<Token, Span>)) {
+    let children = match &expr.kind {
+        ExprKind::Literal(_) | ExprKind::Var(_) => return,
+        ExprKind::Call { args, .. } => args.iter().collect(),
+        ExprKind::Unary { operand, .. } => vec![&**operand],
+        ExprKind::Binary { lhs, rhs, .. } | ExprKind::Logical { lhs, rhs, .. } => {
+            vec![&**lhs, &**rhs]
+        }
+    };
+
+    for child in children {
+        if let ExprKind::Call { ident, .. } = &child.kind {
+            emit(error!(
+                child.span.clone(),
+                format!(
+                    "calling `{ident}` inside an expression is a part of the {} extension, which is disabled",
+                    Extension::FunExp
+                )
+            ));
         }
 
-        _ => Err(Simple::expected_input_found(span, Vec::new(), Some(token))),
-    })
+        report_nested_calls(child, emit);
+    }
 }
 
-pub fn rval() -> impl Parser<Token, RVal, Error = Simple<Token>> + Clone {
-    expression()
-        .map(|expr| RVal::Expr {
-            expr: expression_to_reverse_polish(expr),
-        })
-        .or(function_call())
+pub fn rval(
+    extensions: Extensions,
+) -> impl Parser<Token, Expr, Error = Simple<Token, Span>> + Clone {
+    expression(extensions).validate(move |expr, _, emit| {
+        if !extensions.contains(Extension::FunExp) {
+            report_nested_calls(&expr, emit);
+        }
+
+        expr
+    })
 }
@@ -1,40 +1,267 @@
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, Range};
 
 use crate::syntactic_analysis::ast::Type;
+use crate::{Span, Symbol, WithSpan};
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum Token {
+    Prologue,
+    Epilogue,
     Null,
     Bool(bool),
-    Num(String),
-    Str(String),
-    Op(String),
+    Int(i64),
+    Float(Float),
+    Str(StrId),
+    InterpolatedStr(Vec<StrPart>),
+    Op(Operator),
     Control(char),
-    Ident(String),
+    Ident(Symbol),
+    Var(Symbol),
     Function,
     If,
     Else,
+    ElseIf,
     Return,
     While,
+    For,
+    Break,
+    Continue,
     Type(Type),
+    /// Input the lexer couldn't make sense of, the error has already been reported
+    Error,
 }
 
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            Token::Prologue => write!(f, "<?php declare(strict_types=1);"),
+            Token::Epilogue => write!(f, "?>"),
             Token::Null => write!(f, "null"),
             Token::Bool(n) => write!(f, "{n}"),
-            Token::Num(n) => write!(f, "{n}"),
-            Token::Str(n) => write!(f, "{n}"),
-            Token::Op(n) => write!(f, "{n}"),
+            Token::Int(n) => write!(f, "{n}"),
+            Token::Float(n) => write!(f, "{}", n.0),
+            // The text is in the literals of the file
+            Token::Str(_) | Token::InterpolatedStr(_) => write!(f, "<string>"),
+            Token::Op(n) => Display::fmt(n, f),
             Token::Control(n) => write!(f, "{n}"),
             Token::Ident(n) => write!(f, "{n}"),
+            Token::Var(n) => write!(f, "${n}"),
             Token::Function => write!(f, "function"),
             Token::If => write!(f, "if"),
             Token::Else => write!(f, "else"),
+            Token::ElseIf => write!(f, "elseif"),
             Token::Return => write!(f, "return"),
             Token::While => write!(f, "while"),
+            Token::For => write!(f, "for"),
+            Token::Break => write!(f, "break"),
+            Token::Continue => write!(f, "continue"),
             Token::Type(name) => Display::fmt(name, f),
+            Token::Error => write!(f, "<error>"),
         }
     }
 }
+
+/// Whitespace and comments, they're kept only by the lossless lexer
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Trivia {
+    Whitespace,
+    /// A `//` comment
+    LineComment,
+    /// A `#` comment
+    HashComment,
+    /// A `/* */` comment
+    BlockComment,
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum Lexeme {
+    Token(Token),
+    Trivia(Trivia),
+}
+
+/// A part of a double quoted string containing variables
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum StrPart {
+    Lit(StrId),
+    /// A variable name without the `$`, the span includes it
+    Var(WithSpan<Symbol>),
+}
+
+/// A string literal, its text is stored in the [`Literals`] of the file it was lexed from
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct StrId(u32);
+
+impl StrId {
+    /// Every file's literals start with the empty string
+    pub const EMPTY: StrId = StrId(0);
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Stored {
+    Source(Range<usize>),
+    Arena(Range<usize>),
+}
+
+/// Text of the string literals of a file.
+///
+/// A literal without escapes is a slice of the source, the others are appended to a single arena.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Literals<'src> {
+    src: &'src str,
+    arena: String,
+    stored: Vec<Stored>,
+}
+
+impl<'src> Literals<'src> {
+    pub fn new(src: &'src str) -> Self {
+        Self {
+            src,
+            arena: String::new(),
+            stored: vec![Stored::Arena(0..0)],
+        }
+    }
+
+    pub fn get(&self, id: StrId) -> &str {
+        match &self.stored[id.0 as usize] {
+            Stored::Source(range) => &self.src[range.clone()],
+            Stored::Arena(range) => &self.arena[range.clone()],
+        }
+    }
+
+    /// Every literal with its id, in the order they were lexed
+    pub fn iter(&self) -> impl Iterator<Item = (StrId, &str)> {
+        (0..self.stored.len() as u32).map(|id| (StrId(id), self.get(StrId(id))))
+    }
+
+    /// A literal that is the source text the span points at
+    pub(crate) fn source(&mut self, span: &Span) -> StrId {
+        if span.start >= span.end {
+            return StrId::EMPTY;
+        }
+
+        // A `\r\n` is lexed as a single `\n`, such a literal isn't a slice of the source
+        if self.src[span.range()].contains('\r') {
+            let start = self.start();
+            self.append_source(span);
+            return self.finish(start);
+        }
+
+        self.push(Stored::Source(span.range()))
+    }
+
+    /// Starts a literal built in the arena, it has to be [finished](Literals::finish)
+    pub(crate) fn start(&self) -> usize {
+        self.arena.len()
+    }
+
+    pub(crate) fn append_source(&mut self, span: &Span) {
+        let src = self.src;
+        for (i, line) in src[span.range()].split("\r\n").enumerate() {
+            if i > 0 {
+                self.arena.push('\n');
+            }
+
+            self.arena.push_str(line);
+        }
+    }
+
+    pub(crate) fn append(&mut self, chars: impl IntoIterator<Item = char>) {
+        self.arena.extend(chars);
+    }
+
+    pub(crate) fn finish(&mut self, start: usize) -> StrId {
+        if start == self.arena.len() {
+            return StrId::EMPTY;
+        }
+
+        self.push(Stored::Arena(start..self.arena.len()))
+    }
+
+    fn push(&mut self, stored: Stored) -> StrId {
+        self.stored.push(stored);
+
+        StrId(self.stored.len() as u32 - 1)
+    }
+}
+
+impl Index<StrId> for Literals<'_> {
+    type Output = str;
+
+    fn index(&self, id: StrId) -> &str {
+        self.get(id)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Operator {
+    Assign,
+    Equals,
+    NotEquals,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Concat,
+    Not,
+    // BOOLTHEN extension
+    And,
+    Or,
+    /// The `and` keyword, binds looser than `&&`
+    AndKeyword,
+    /// The `or` keyword, binds looser than `||`
+    OrKeyword,
+}
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Assign => "=",
+            Operator::Equals => "===",
+            Operator::NotEquals => "!==",
+            Operator::Less => "<",
+            Operator::LessEq => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEq => ">=",
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Concat => ".",
+            Operator::Not => "!",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::AndKeyword => "and",
+            Operator::OrKeyword => "or",
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A float compared and hashed by its bit pattern so it can be a part of a [`Token`]
+#[derive(Debug, Copy, Clone)]
+pub struct Float(pub f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Float {}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state)
+    }
+}
@@ -1,6 +1,10 @@
+// Every test crate compiles this module but none of them uses all of it
+#![allow(dead_code)]
+
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use rust_ifj22_compiler::source_map::{FileId, SourceMap};
 use walkdir::{DirEntry, WalkDir};
 
 pub fn load_test_code<P: AsRef<Path>>(file_name: P) -> String {
@@ -35,3 +39,11 @@ pub fn load_test_group<P: AsRef<Path>>(folder_name: P) -> Vec<(String, String)>
         .filter_map(load_dir_entry)
         .collect()
 }
+
+/// Puts the source into a map of its own so it can be lexed and reported on
+pub fn source_map(name: &str, src: &str) -> (SourceMap, FileId) {
+    let mut map = SourceMap::new();
+    let id = map.add(name, src);
+
+    (map, id)
+}
@@ -0,0 +1,85 @@
+use std::process::{Command, Output};
+
+fn compile(file_name: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rust_ifj22_compiler"))
+        .args(args)
+        .arg(format!("tests/common/{file_name}"))
+        .output()
+        .expect("Failed to run the compiler")
+}
+
+#[test]
+fn lexical_and_syntax_errors() {
+    let output = compile("diagnostics/lexical_and_syntax.txt", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // The parser runs past the bad lexeme and reports the broken assignment after it
+    assert!(
+        stderr.contains("unexpected characters `@`"),
+        "Missing the lexical error in {stderr}"
+    );
+    assert!(
+        stderr.contains("Unexpected token, expected ("),
+        "Missing the syntax error in {stderr}"
+    );
+
+    // The error token itself isn't reported a second time by the parser
+    assert_eq!(stderr.matches("Error:").count(), 2, "{stderr}");
+
+    // A lexical error takes precedence over the syntax one
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn quiet_stdout() {
+    let output = compile("parser/valid/statements.txt", &[]);
+
+    assert!(output.status.success());
+    assert!(
+        output.stdout.is_empty(),
+        "Stdout is reserved for the compiled program"
+    );
+}
+
+#[test]
+fn function_errors() {
+    let output = compile("semantic/functions.txt", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(
+        stderr.contains("call to undefined function `undefined`"),
+        "Missing the undefined function in {stderr}"
+    );
+    assert!(
+        stderr.contains("function `LATE` is already declared"),
+        "Missing the redeclaration in {stderr}"
+    );
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn loop_control_errors() {
+    let output = compile("semantic/loop_control.txt", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(
+        stderr.contains("`break` outside of a loop"),
+        "Missing the loop control error in {stderr}"
+    );
+    assert_eq!(output.status.code(), Some(8));
+}
+
+#[test]
+fn casing_warnings() {
+    let quiet = compile("identifiers/casing.txt", &[]);
+    assert!(quiet.status.success());
+    assert!(quiet.stderr.is_empty(), "Casing warnings are opt-in");
+
+    let output = compile("identifiers/casing.txt", &["--warn-casing"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Warnings alone don't fail the compilation
+    assert!(output.status.success());
+    // Three calls, the message is both the title and the label of a report
+    assert_eq!(stderr.matches("differs in casing").count(), 6, "{stderr}");
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use chumsky::prelude::Parser;
+use rust_ifj22_compiler::lexer;
+use rust_ifj22_compiler::lexer::fast_lexer;
+
+fn assert_same_output(file: &str, src: &str) {
+    let (map, id) = common::source_map(file, src);
+
+    let expected = lexer(&map[id]).parse_recovery(map[id].stream());
+    let found = fast_lexer(&map[id]);
+
+    assert_eq!(
+        found.0, expected.0,
+        "Fast lexer produced different tokens for {file}"
+    );
+    assert_eq!(
+        found.1, expected.1,
+        "Fast lexer produced different diagnostics for {file}"
+    );
+}
+
+#[test]
+fn same_output() {
+    for (file, src) in common::load_test_group("") {
+        assert_same_output(&file, &src);
+    }
+}
+
+#[test]
+fn same_output_at_end_of_input() {
+    // Cutting the files short tests how both lexers deal with unterminated constructs
+    for (file, src) in common::load_test_group("") {
+        // Every cut of the test helpers would take too long
+        if file == "mod.rs" {
+            continue;
+        }
+
+        for (end, _) in src.char_indices() {
+            assert_same_output(&format!("{file} cut at byte {end}"), &src[..end]);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::process::Command;
+
+use chumsky::error::SimpleReason;
+use rust_ifj22_compiler::lexer::{lex, LexerKind};
+use rust_ifj22_compiler::source_map::SourceMap;
+use rust_ifj22_compiler::token::{Lexeme, Trivia};
+use rust_ifj22_compiler::{lexer, Token};
+
+const KINDS: [LexerKind; 2] = [LexerKind::Combinator, LexerKind::Fast];
+
+#[test]
+fn crlf() {
+    let file_name = "input/crlf.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    for kind in KINDS {
+        let (tokens, errs) = lex(&map[id], kind);
+        assert!(errs.is_empty(), "{kind:?} lexer reported {errs:?}");
+
+        let (tokens, literals) = tokens.unwrap();
+        let heredoc = tokens.iter().find_map(|(tok, span)| match tok {
+            Token::Str(lit) if *span == map[id].span(52..78) => Some(&literals[*lit]),
+            _ => None,
+        });
+        assert_eq!(
+            heredoc,
+            Some("Hello"),
+            "{kind:?} lexer kept the `\\r` in the heredoc"
+        );
+        assert_eq!(tokens.last().map(|(tok, _)| tok), Some(&Token::Epilogue));
+    }
+
+    // A line ending is a single whitespace character spanning both bytes
+    let newline = map[id].stream().fetch_tokens().nth(5);
+    assert_eq!(newline, Some(('\n', map[id].span(5..7))));
+}
+
+#[test]
+fn crlf_comments() {
+    use chumsky::prelude::Parser;
+
+    let file_name = "input/crlf.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let (lexemes, _) = rust_ifj22_compiler::lexer::lossless_lexer(&map[id])
+        .parse(map[id].stream())
+        .unwrap();
+
+    // The comments end in front of the line ending
+    for (lexeme, span) in lexemes {
+        if let Lexeme::Trivia(Trivia::LineComment | Trivia::HashComment) = lexeme {
+            assert!(!map[id].slice(&span).contains('\r'));
+        }
+    }
+}
+
+#[test]
+fn bom() {
+    let file_name = "input/bom.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    for kind in KINDS {
+        let (tokens, errs) = lex(&map[id], kind);
+
+        assert_eq!(errs.len(), 1, "{kind:?} lexer reported {errs:?}");
+        assert_eq!(errs[0].span(), map[id].span(0..3));
+        assert!(
+            matches!(errs[0].reason(), SimpleReason::Custom(msg) if msg.starts_with("Warn:")),
+            "{kind:?} lexer didn't warn about the byte order mark"
+        );
+
+        assert_eq!(
+            tokens.unwrap().0.first(),
+            Some(&(Token::Prologue, map[id].span(0..33)))
+        );
+    }
+}
+
+#[test]
+fn invalid_utf8() {
+    let mut map = SourceMap::new();
+    let id = map
+        .load("tests/common/input/latin2.txt")
+        .expect("Failed to read the Latin-2 test file");
+
+    // Spans point at the original bytes, the replacement keeps the rest of the file in place
+    assert_eq!(map[id].invalid_utf8().first(), Some(&map[id].span(39..40)));
+    assert_eq!(map[id].invalid_utf8().len(), 9);
+    assert_eq!(map[id].slice(&map[id].span(41..44)), "li?");
+
+    for kind in KINDS {
+        let (tokens, errs) = lex(&map[id], kind);
+
+        assert_eq!(tokens, None, "{kind:?} lexer lexed an invalid file");
+        assert_eq!(
+            errs.iter().map(|err| err.span()).collect::<Vec<_>>(),
+            map[id].invalid_utf8()
+        );
+    }
+
+    // Valid UTF-8 stays untouched
+    let valid = map.add_bytes("valid.php", "<?php ž".as_bytes().to_vec());
+    assert!(map[valid].invalid_utf8().is_empty());
+    assert_eq!(map[valid].src(), "<?php ž");
+
+    // A sequence cut short by the end of the file
+    let cut = map.add_bytes("cut.php", b"<?php \xC5".to_vec());
+    assert_eq!(map[cut].invalid_utf8(), [map[cut].span(6..7)]);
+}
+
+#[test]
+fn invalid_utf8_exit_code() {
+    let status = Command::new(env!("CARGO_BIN_EXE_rust_ifj22_compiler"))
+        .arg("tests/common/input/latin2.txt")
+        .output()
+        .expect("Failed to run the compiler")
+        .status;
+
+    assert_eq!(status.code(), Some(1));
+}
+
+#[test]
+fn lexer_sees_normalized_newlines() {
+    use chumsky::prelude::Parser;
+
+    let mut map = SourceMap::new();
+    let id = map.add("crlf.php", "<?php\r\ndeclare(strict_types=1);\r\n?>\r\n");
+
+    let (tokens, errs) = lexer(&map[id]).parse_recovery(map[id].stream());
+    assert!(errs.is_empty(), "{errs:?}");
+    assert_eq!(
+        tokens.unwrap().0.last(),
+        Some(&(Token::Epilogue, map[id].span(33..37)))
+    );
+}
@@ -1,20 +1,94 @@
 mod common;
 
+use chumsky::error::SimpleReason;
 use chumsky::prelude::Parser;
-use rust_ifj22_compiler::lexer;
-use rust_ifj22_compiler::lexer::print_lexer_errors;
+use rust_ifj22_compiler::lexer::{lossless_lexer, print_errors};
+use rust_ifj22_compiler::syntactic_analysis::ast::Type;
+use rust_ifj22_compiler::token::{Float, Lexeme, Literals, Operator, StrPart, Trivia};
+use rust_ifj22_compiler::{lexer, Span, Symbol, Token, WithSpan};
+
+/// A token with the text of its string literals, the ids mean nothing without the file's literals
+#[derive(Debug, PartialEq)]
+enum Lexed {
+    Token(Token),
+    Str(String),
+    InterpolatedStr(Vec<Part>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Part {
+    Lit(String),
+    Var(WithSpan<Symbol>),
+}
+
+fn resolve(tokens: Vec<(Token, Span)>, literals: &Literals) -> Vec<Lexed> {
+    tokens
+        .into_iter()
+        .map(|(tok, _)| match tok {
+            Token::Str(lit) => Lexed::Str(literals[lit].to_string()),
+            Token::InterpolatedStr(parts) => Lexed::InterpolatedStr(
+                parts
+                    .into_iter()
+                    .map(|part| match part {
+                        StrPart::Lit(lit) => Part::Lit(literals[lit].to_string()),
+                        StrPart::Var(var) => Part::Var(var),
+                    })
+                    .collect(),
+            ),
+            tok => Lexed::Token(tok),
+        })
+        .collect()
+}
 
 #[test]
 fn empty_file() {
     let file_name = "empty.txt";
     let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    // Even an empty program has to start with the prologue
+    if let Ok(tokens) = lex.parse(map[id].stream()) {
+        println!("{:?}", tokens);
+        panic!("Lexer failed to spot the missing prologue in an empty file!");
+    }
+}
+
+#[test]
+fn valid_prologue() {
+    let group_name = "prologue/valid";
+    let files = common::load_test_group(group_name);
 
-    let lex = lexer();
+    for (file, src) in files {
+        let (map, id) = common::source_map(&file, &src);
+        let lex = lexer(&map[id]);
+        match lex.parse(map[id].stream()) {
+            Ok((tokens, _)) => assert_eq!(
+                tokens.first().map(|(tok, _)| tok),
+                Some(&Token::Prologue),
+                "Lexer didn't emit the prologue in file {file}"
+            ),
+            Err(errs) => {
+                print_errors(errs, &map);
+                panic!("Lexer failed to lex a valid prologue in file {file}");
+            }
+        }
+    }
+}
 
-    let result = lex.parse(file.as_str());
-    if let Err(errs) = result {
-        print_lexer_errors(errs, &file, file_name);
-        panic!("Lexer failed to lex an empty file!");
+#[test]
+fn invalid_prologue() {
+    let group_name = "prologue/invalid";
+    let files = common::load_test_group(group_name);
+
+    for (file, src) in files {
+        let (map, id) = common::source_map(&file, &src);
+        let lex = lexer(&map[id]);
+        if let Ok(tokens) = lex.parse(map[id].stream()) {
+            println!("{:?}", tokens);
+            panic!("Lexer failed to spot the invalid prologue or epilogue in file {file}");
+        }
     }
 }
 
@@ -23,12 +97,597 @@ fn unexpected_char() {
     let group_name = "unexpected_chars";
     let files = common::load_test_group(group_name);
 
-    let lex = lexer();
-
     for (file, src) in files {
-        if let Ok(tree) = lex.parse(src) {
+        let (map, id) = common::source_map(&file, &src);
+        let lex = lexer(&map[id]);
+        if let Ok(tree) = lex.parse(map[id].stream()) {
             println!("{:?}", tree);
             panic!("Lexer failed to spot the mistake in file {file}");
         }
     }
 }
+
+#[test]
+fn types() {
+    let file_name = "types/signatures.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, _)) => tokens,
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex function signatures");
+        }
+    };
+
+    let types = tokens
+        .into_iter()
+        .filter_map(|(tok, _)| match tok {
+            Token::Type(r#type) => Some(r#type),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        types,
+        [
+            Type::Int { nullable: true },
+            Type::Float { nullable: false },
+            Type::String { nullable: false },
+            Type::Float { nullable: true },
+            Type::String { nullable: true },
+            Type::Void,
+        ]
+    );
+}
+
+#[test]
+fn nullable_void() {
+    let file = common::load_test_code("types/nullable_void.txt");
+    let (map, id) = common::source_map("types/nullable_void.txt", &file);
+
+    let lex = lexer(&map[id]);
+
+    if let Ok(tokens) = lex.parse(map[id].stream()) {
+        println!("{:?}", tokens);
+        panic!("Lexer failed to spot a nullable void type");
+    }
+}
+
+#[test]
+fn operators() {
+    use Operator::*;
+
+    let file_name = "operators/maximal_munch.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, _)) => tokens,
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex operators");
+        }
+    };
+
+    let operators = tokens
+        .into_iter()
+        .filter_map(|(tok, _)| match tok {
+            Token::Op(op) => Some(op),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        operators,
+        [
+            Assign, Subtract, Assign, NotEquals, Assign, LessEq, Concat, Assign, Not, And, Or,
+            GreaterEq, Multiply, Divide, Add,
+        ]
+    );
+}
+
+/// Lexes with recovery, returns the tokens after the prologue and the source and message of every error
+fn lex_recovery(file: &str, src: &str) -> (Vec<Lexed>, Vec<(String, String)>) {
+    let (map, id) = common::source_map(file, src);
+    let (lexed, errs) = lexer(&map[id]).parse_recovery(map[id].stream());
+
+    let (tokens, literals) =
+        lexed.unwrap_or_else(|| panic!("Lexer failed to recover in file {file}"));
+    let tokens = resolve(tokens, &literals).into_iter().skip(1).collect();
+    let errs = errs
+        .iter()
+        .map(|err| match err.reason() {
+            SimpleReason::Custom(msg) => (map[id].slice(&err.span()).to_string(), msg.clone()),
+            reason => panic!("Expected a custom error in file {file}, found {reason:?}"),
+        })
+        .collect();
+
+    (tokens, errs)
+}
+
+#[test]
+fn unsupported_operators() {
+    let group_name = "operators/unsupported";
+    let files = common::load_test_group(group_name);
+
+    let var = |name: &str| Token::Var(name.into());
+
+    for (file, src) in files {
+        // The operator is replaced by the closest supported one and lexing goes on
+        let (seq, msg, recovered) = match file.as_str() {
+            "loose_equality.txt" => (
+                "==",
+                "loose comparison isn't supported, use `===` instead",
+                [var("b"), Token::Op(Operator::Equals), var("c")].map(Lexed::Token),
+            ),
+            "loose_inequality.txt" => (
+                "!=",
+                "loose comparison isn't supported, use `!==` instead",
+                [var("b"), Token::Op(Operator::NotEquals), var("c")].map(Lexed::Token),
+            ),
+            "exponentiation.txt" => (
+                "**",
+                "the exponentiation operator isn't supported",
+                [var("a"), Token::Op(Operator::Multiply), Token::Int(2)].map(Lexed::Token),
+            ),
+            "compound_assignment.txt" => (
+                ".=",
+                "compound assignment isn't supported",
+                [
+                    Lexed::Token(Token::Op(Operator::Assign)),
+                    Lexed::Str("x".into()),
+                    Lexed::Token(Token::Control(';')),
+                ],
+            ),
+            _ => panic!("No expectations for file {file}"),
+        };
+
+        let (tokens, errs) = lex_recovery(&file, &src);
+        assert_eq!(
+            errs,
+            [(seq.to_string(), format!("Error:{msg}"))],
+            "Wrong diagnostic in file {file}"
+        );
+        assert!(
+            tokens.windows(3).any(|window| window == recovered),
+            "Expected {recovered:?} in {tokens:?} of file {file}"
+        );
+    }
+}
+
+#[test]
+fn identifiers() {
+    let file_name = "identifiers/names.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, _)) => tokens,
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex identifiers");
+        }
+    };
+
+    let names = tokens
+        .into_iter()
+        .filter(|(tok, _)| matches!(tok, Token::Ident(_) | Token::Var(_)))
+        .map(|(tok, _)| tok)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        names,
+        [
+            Token::Var("x1".into()),
+            Token::Ident("foo2".into()),
+            Token::Var("_y".into()),
+            Token::Var("__z3".into()),
+            Token::Ident("_helper".into()),
+            Token::Var("camelCase".into()),
+        ]
+    );
+}
+
+#[test]
+fn invalid_variables() {
+    let group_name = "identifiers/invalid";
+    let files = common::load_test_group(group_name);
+
+    for (file, src) in files {
+        let (map, id) = common::source_map(&file, &src);
+        let lex = lexer(&map[id]);
+        if let Ok(tokens) = lex.parse(map[id].stream()) {
+            println!("{:?}", tokens);
+            panic!("Lexer failed to spot the invalid variable in file {file}");
+        }
+    }
+}
+
+#[test]
+fn numbers() {
+    let file_name = "numbers/literals.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, _)) => tokens,
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex numeric literals");
+        }
+    };
+
+    let numbers = tokens
+        .into_iter()
+        .filter(|(tok, _)| matches!(tok, Token::Int(_) | Token::Float(_)))
+        .map(|(tok, _)| tok)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        numbers,
+        [
+            Token::Int(0x1F),
+            Token::Int(0o17),
+            Token::Int(0o17),
+            Token::Int(0b101),
+            Token::Int(1_000_000),
+            Token::Int(i64::MAX),
+            Token::Float(Float(0.5)),
+            Token::Float(Float(1.0)),
+            Token::Float(Float(1.5e3)),
+            Token::Float(Float(2E-2)),
+            Token::Float(Float(10.25)),
+        ]
+    );
+}
+
+#[test]
+fn invalid_numbers() {
+    let group_name = "numbers/invalid";
+    let files = common::load_test_group(group_name);
+
+    for (file, src) in files {
+        // The span covers only the offending part unless the whole literal is wrong
+        let (junk, msg, int) = match file.as_str() {
+            "binary_digit.txt" => ("2", "invalid numeric literal", 0b10),
+            "empty_hex.txt" => ("x", "expected digits after the `0x` prefix", 0),
+            "missing_exponent.txt" => ("e", "expected digits after the exponent", 1),
+            "octal_digit.txt" => ("08", "invalid digit in an octal literal", 0),
+            "overflow.txt" => (
+                "9223372036854775808",
+                "integer literal doesn't fit into a 64 bit signed integer",
+                0,
+            ),
+            "trailing_separator.txt" => (
+                "_",
+                "numeric literal separators have to be placed between digits",
+                1,
+            ),
+            _ => panic!("No expectations for file {file}"),
+        };
+
+        let (tokens, errs) = lex_recovery(&file, &src);
+        assert_eq!(
+            errs,
+            [(junk.to_string(), format!("Error:{msg}"))],
+            "Wrong diagnostic in file {file}"
+        );
+        assert_eq!(
+            tokens,
+            [
+                Token::Var("a".into()),
+                Token::Op(Operator::Assign),
+                Token::Int(int),
+                Token::Control(';'),
+            ]
+            .map(Lexed::Token),
+            "Wrong recovery in file {file}"
+        );
+    }
+}
+
+#[test]
+fn escapes() {
+    let file_name = "strings/escapes.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, literals)) => resolve(tokens, &literals),
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex escape sequences");
+        }
+    };
+
+    let strings = tokens
+        .into_iter()
+        .filter(|lexed| matches!(lexed, Lexed::Str(_)))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        strings,
+        [Lexed::Str("a$b\\q\0\nAA\u{4}\u{1F600}\\\"".into())]
+    );
+}
+
+#[test]
+fn invalid_strings() {
+    let group_name = "strings/invalid";
+    let files = common::load_test_group(group_name);
+
+    for (file, src) in files {
+        let (map, id) = common::source_map(&file, &src);
+        let lex = lexer(&map[id]);
+        if let Ok(tokens) = lex.parse(map[id].stream()) {
+            println!("{:?}", tokens);
+            panic!("Lexer failed to spot the invalid string in file {file}");
+        }
+    }
+}
+
+#[test]
+fn escape_typos() {
+    let group_name = "strings/typos";
+    let files = common::load_test_group(group_name);
+
+    for (file, src) in files {
+        let (map, id) = common::source_map(&file, &src);
+        let lex = lexer(&map[id]);
+        match lex.parse(map[id].stream()) {
+            Ok(tokens) => {
+                println!("{:?}", tokens);
+                panic!("Lexer didn't warn about the escape sequence in file {file}");
+            }
+            Err(errs) => {
+                for err in errs {
+                    assert!(
+                        matches!(err.reason(), SimpleReason::Custom(msg) if msg.starts_with("Warn:")),
+                        "Lexer reported {err:?} instead of a warning in file {file}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn interpolation() {
+    let file_name = "strings/interpolation.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, literals)) => resolve(tokens, &literals),
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex interpolated strings");
+        }
+    };
+
+    let strings = tokens
+        .into_iter()
+        .filter(|lexed| matches!(lexed, Lexed::Str(_) | Lexed::InterpolatedStr(_)))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        strings,
+        [
+            Lexed::InterpolatedStr(vec![
+                Part::Lit("Hello ".into()),
+                Part::Var(WithSpan(map[id].span(43..48), "name".into())),
+                Part::Lit("\n".into()),
+            ]),
+            Lexed::Str("$name $".into()),
+        ]
+    );
+}
+
+#[test]
+fn heredoc() {
+    let file_name = "strings/heredoc.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, literals)) => resolve(tokens, &literals),
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex heredoc strings");
+        }
+    };
+
+    let strings = tokens
+        .into_iter()
+        .filter(|lexed| matches!(lexed, Lexed::Str(_) | Lexed::InterpolatedStr(_)))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        strings,
+        [
+            Lexed::InterpolatedStr(vec![
+                Part::Lit("Hello ".into()),
+                Part::Var(WithSpan(map[id].span(53..58), "name".into())),
+                Part::Lit("\n  \"indented\"\t\\\"\n".into()),
+            ]),
+            Lexed::Str("raw $name \\n".into()),
+            Lexed::Str("".into()),
+        ]
+    );
+}
+
+#[test]
+fn error_recovery() {
+    let file = common::load_test_code("recovery/multiple_errors.txt");
+    let (map, id) = common::source_map("recovery/multiple_errors.txt", &file);
+
+    let lex = lexer(&map[id]);
+
+    let (lexed, errs) = lex.parse_recovery(map[id].stream());
+    let (tokens, literals) = lexed.expect("Lexer failed to recover");
+    let tokens = resolve(tokens, &literals);
+
+    // Unterminated string, unexpected characters and an unterminated block comment
+    assert_eq!(errs.len(), 3, "{errs:?}");
+    assert_eq!(
+        tokens[1..],
+        [
+            Lexed::Token(Token::Var("a".into())),
+            Lexed::Token(Token::Op(Operator::Assign)),
+            Lexed::Str("abc;".into()),
+            Lexed::Token(Token::Var("b".into())),
+            Lexed::Token(Token::Op(Operator::Assign)),
+            Lexed::Token(Token::Error),
+            Lexed::Token(Token::Int(1)),
+            Lexed::Token(Token::Control(';')),
+        ]
+    );
+}
+
+/// Concatenates the source text covered by the spans, they have to follow each other without gaps
+fn reconstruct(src: &str, spans: impl IntoIterator<Item = Span>) -> String {
+    let mut end = 0;
+
+    spans
+        .into_iter()
+        .map(|span| {
+            assert_eq!(
+                span.start, end,
+                "Lexemes don't cover the input without gaps"
+            );
+            end = span.end;
+            &src[span.range()]
+        })
+        .collect()
+}
+
+#[test]
+fn lossless() {
+    let file = common::load_test_code("lossless/trivia.txt");
+    let (map, id) = common::source_map("lossless/trivia.txt", &file);
+
+    let lex = lossless_lexer(&map[id]);
+    let (lexemes, literals) = match lex.parse(map[id].stream()) {
+        Ok(lexed) => lexed,
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lossless lexer failed to lex valid code");
+        }
+    };
+
+    let trivia = lexemes
+        .iter()
+        .filter_map(|(lexeme, _)| match lexeme {
+            Lexeme::Trivia(trivia) if *trivia != Trivia::Whitespace => Some(*trivia),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        trivia,
+        [
+            Trivia::LineComment,
+            Trivia::HashComment,
+            Trivia::BlockComment,
+            Trivia::BlockComment,
+            Trivia::HashComment
+        ]
+    );
+
+    // Filtering out the trivia gives the same tokens the normal lexer does
+    let tokens = lexemes
+        .into_iter()
+        .filter_map(|(lexeme, span)| match lexeme {
+            Lexeme::Token(tok) => Some((tok, span)),
+            Lexeme::Trivia(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let lex = lexer(&map[id]);
+    assert_eq!(Ok((tokens, literals)), lex.parse(map[id].stream()));
+}
+
+#[test]
+fn lossless_round_trip() {
+    // Every test file, even the invalid ones, has to survive the round trip
+    let files = common::load_test_group("");
+
+    for (file, src) in files {
+        let (map, id) = common::source_map(&file, &src);
+        let lex = lossless_lexer(&map[id]);
+        let (lexemes, _) = lex.parse_recovery(map[id].stream());
+
+        // Nothing can be lexed without the prologue
+        let Some((lexemes, _)) = lexemes else {
+            continue;
+        };
+
+        assert_eq!(
+            reconstruct(&src, lexemes.into_iter().map(|(_, span)| span)),
+            src,
+            "Lossless lexer didn't reproduce file {file}"
+        );
+    }
+}
+
+#[test]
+fn case_insensitive_keywords() {
+    let file_name = "identifiers/casing.txt";
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+
+    let lex = lexer(&map[id]);
+    let tokens = match lex.parse(map[id].stream()) {
+        Ok((tokens, _)) => tokens,
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex differently cased keywords");
+        }
+    };
+
+    let tokens = tokens.into_iter().map(|(tok, _)| tok).collect::<Vec<_>>();
+
+    for keyword in [
+        Token::Function,
+        Token::If,
+        Token::Else,
+        Token::ElseIf,
+        Token::Return,
+        Token::While,
+        Token::For,
+        Token::Break,
+        Token::Continue,
+        Token::Null,
+        Token::Bool(true),
+        Token::Type(Type::String { nullable: true }),
+        Token::Type(Type::Void),
+        Token::Op(Operator::AndKeyword),
+        Token::Op(Operator::OrKeyword),
+    ] {
+        assert!(
+            tokens.contains(&keyword),
+            "Lexer didn't recognize {keyword}"
+        );
+    }
+
+    // Function names keep their casing, variables stay case sensitive
+    assert!(tokens.contains(&Token::Ident("Greet".into())));
+    assert!(tokens.contains(&Token::Var("Name".into())));
+    assert!(tokens.contains(&Token::Var("name".into())));
+}
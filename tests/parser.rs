@@ -0,0 +1,792 @@
+mod common;
+
+use chumsky::error::{Simple, SimpleReason};
+use chumsky::{Parser, Stream};
+use rust_ifj22_compiler::lexer::print_errors;
+use rust_ifj22_compiler::source_map::SourceMap;
+use rust_ifj22_compiler::syntactic_analysis::ast::{
+    BinaryOp, Expr, ExprKind, Expression, For, FuncDef, If, Item, Literal, LogicalOp, Stmt, Term,
+    Type, UnaryOp, While,
+};
+use rust_ifj22_compiler::syntactic_analysis::{parser, Extension, Extensions};
+use rust_ifj22_compiler::token::StrId;
+use rust_ifj22_compiler::{lexer, Span, Token};
+
+type ParseResult = (Option<Vec<Item>>, Vec<Simple<Token, Span>>);
+
+/// The string literals of the parsed file, they borrow the source map so they're copied out of it
+type Strings = Vec<(StrId, String)>;
+
+/// Lexes and parses the file with every extension enabled, the lexer isn't allowed to fail
+fn parse(file: &str, src: &str) -> (SourceMap, ParseResult) {
+    parse_with(file, src, Extensions::default())
+}
+
+fn parse_with(file: &str, src: &str, extensions: Extensions) -> (SourceMap, ParseResult) {
+    let (map, result, _) = parse_strings(file, src, extensions);
+    (map, result)
+}
+
+fn parse_strings(
+    file: &str,
+    src: &str,
+    extensions: Extensions,
+) -> (SourceMap, ParseResult, Strings) {
+    let (map, id) = common::source_map(file, src);
+
+    let (tokens, literals) = match lexer(&map[id]).parse(map[id].stream()) {
+        Ok(lexed) => lexed,
+        Err(errs) => {
+            print_errors(errs, &map);
+            panic!("Lexer failed to lex file {file}");
+        }
+    };
+
+    let strings = literals
+        .iter()
+        .map(|(id, text)| (id, text.to_string()))
+        .collect();
+
+    let result =
+        parser(extensions).parse_recovery(Stream::from_iter(map[id].eoi(), tokens.into_iter()));
+    (map, result, strings)
+}
+
+/// A string term with the given text
+fn string(strings: &Strings, text: &str) -> Expression {
+    let Some((id, _)) = strings.iter().find(|(_, lit)| lit == text) else {
+        panic!("Expected a string literal {text:?} in {strings:?}");
+    };
+
+    Expression::Term(Term::String(*id))
+}
+
+#[test]
+fn valid_programs() {
+    let files = common::load_test_group("parser/valid");
+
+    for (file, src) in files {
+        let (map, (program, errs)) = parse(&file, &src);
+
+        if !errs.is_empty() {
+            print_errors(errs, &map);
+            panic!("Parser failed to parse file {file}");
+        }
+
+        assert!(program.is_some());
+    }
+}
+
+/// The top level statements of a program without any function definitions
+fn statements(program: Vec<Item>) -> Vec<Stmt> {
+    program
+        .into_iter()
+        .map(|item| match item {
+            Item::Stmt(stmt) => stmt,
+            Item::FuncDef(func) => panic!("Unexpected function definition {func:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn statements_and_spans() {
+    let file_name = "parser/valid/statements.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = statements(program.unwrap());
+    let file = map.files().next().unwrap();
+
+    assert_eq!(
+        program[0],
+        Stmt::VarAssignment {
+            span: file.span(31..42),
+            ident: "a".into(),
+            expression: Expr::new(
+                file.span(36..41),
+                ExprKind::Binary {
+                    op: BinaryOp::Add,
+                    lhs: Box::new(Expr::new(
+                        file.span(36..37),
+                        ExprKind::Literal(Literal::Int(1))
+                    )),
+                    rhs: Box::new(Expr::new(
+                        file.span(40..41),
+                        ExprKind::Literal(Literal::Int(2))
+                    )),
+                },
+            ),
+        }
+    );
+
+    assert!(matches!(
+        &program[1],
+        Stmt::Exp { expr: Expr { kind: ExprKind::Call { ident, args }, .. }, .. } if ident.as_str() == "write" && args.len() == 3
+    ));
+    assert!(matches!(
+        &program[2],
+        Stmt::VarAssignment {
+            expression: Expr {
+                kind: ExprKind::Call { .. },
+                ..
+            },
+            ..
+        }
+    ));
+    assert!(matches!(
+        program[3],
+        Stmt::Exp {
+            expr: Expr {
+                kind: ExprKind::Var(_),
+                ..
+            },
+            ..
+        }
+    ));
+    assert!(matches!(program[4], Stmt::Return { expr: None, .. }));
+    assert_eq!(program.len(), 5);
+
+    // Every statement points at its own source
+    let sources = program
+        .iter()
+        .map(|stmt| file.slice(stmt.span()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sources,
+        [
+            "$a = 1 + 2;",
+            "write(\"sum: \", $a, \"\\n\");",
+            "$b = strlen(\"abc\");",
+            "$a;",
+            "return;"
+        ]
+    );
+}
+
+#[test]
+fn expressions() {
+    let file_name = "parser/valid/expressions.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs), strings) = parse_strings(file_name, &file, Extensions::default());
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = statements(program.unwrap());
+    let file = map.files().next().unwrap();
+
+    let Stmt::VarAssignment { expression, .. } = &program[0] else {
+        panic!("Expected an assignment, found {:?}", program[0]);
+    };
+
+    // Binary operators span both of their operands
+    assert_eq!(file.slice(&expression.span), "($a + 2) * 3 . \"x $b\"");
+    let ExprKind::Binary { op, lhs, rhs } = &expression.kind else {
+        panic!("Expected a binary expression, found {expression:?}");
+    };
+    assert_eq!(*op, BinaryOp::Concat);
+    assert_eq!(file.slice(&lhs.span), "($a + 2) * 3");
+    assert_eq!(file.slice(&rhs.span), "\"x $b\"");
+
+    let term = |name: &str| Expression::Term(Term::Var(name.into()));
+    let string = |lit: &str| string(&strings, lit);
+    assert_eq!(
+        expression.to_reverse_polish(),
+        [
+            term("a"),
+            Expression::Term(Term::Int(2)),
+            Expression::Add,
+            Expression::Term(Term::Int(3)),
+            Expression::Multiply,
+            string("x "),
+            term("b"),
+            Expression::Concat,
+            Expression::Concat,
+        ]
+    );
+
+    let Stmt::Exp { expr, .. } = &program[1] else {
+        panic!("Expected an expression statement, found {:?}", program[1]);
+    };
+    assert_eq!(file.slice(&expr.span), "write($c, 1 < 2)");
+    assert_eq!(
+        expr.to_reverse_polish(),
+        [
+            term("c"),
+            Expression::Term(Term::Int(1)),
+            Expression::Term(Term::Int(2)),
+            Expression::Smaller,
+            Expression::Call {
+                ident: "write".into(),
+                args: 2
+            },
+        ]
+    );
+}
+
+#[test]
+fn logical_operators() {
+    let file_name = "parser/valid/logical.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = statements(program.unwrap());
+    let file = map.files().next().unwrap();
+
+    let Stmt::VarAssignment { expression, .. } = &program[0] else {
+        panic!("Expected an assignment, found {:?}", program[0]);
+    };
+
+    // `||` binds looser than `&&`, which binds looser than the comparison
+    let ExprKind::Logical {
+        op: LogicalOp::Or,
+        lhs,
+        ..
+    } = &expression.kind
+    else {
+        panic!("Expected `||`, found {expression:?}");
+    };
+    let ExprKind::Logical {
+        op: LogicalOp::And,
+        lhs,
+        ..
+    } = &lhs.kind
+    else {
+        panic!("Expected `&&`, found {lhs:?}");
+    };
+    assert!(matches!(
+        lhs.kind,
+        ExprKind::Unary {
+            op: UnaryOp::Not,
+            ..
+        }
+    ));
+    assert_eq!(file.slice(&lhs.span), "!$b");
+
+    let var = |name: &str| Expression::Term(Term::Var(name.into()));
+    assert_eq!(
+        expression.to_reverse_polish(),
+        [
+            var("b"),
+            Expression::Not,
+            Expression::And { skip: 4 },
+            var("c"),
+            Expression::Negate,
+            Expression::Term(Term::Int(2)),
+            Expression::Smaller,
+            Expression::Or { skip: 1 },
+            var("d"),
+        ]
+    );
+
+    // The keywords bind looser than `&&`
+    let Stmt::If(If { expr, .. }) = &program[1] else {
+        panic!("Expected an if statement, found {:?}", program[1]);
+    };
+    assert_eq!(
+        expr.to_reverse_polish(),
+        [
+            var("a"),
+            Expression::And { skip: 1 },
+            var("b"),
+            Expression::Or { skip: 3 },
+            var("c"),
+            Expression::And { skip: 1 },
+            var("d"),
+        ]
+    );
+}
+
+#[test]
+fn disabled_extensions() {
+    let file_name = "parser/valid/logical.txt";
+    let file = common::load_test_code(file_name);
+
+    let (_, (program, errs)) = parse_with(file_name, &file, Extensions::NONE);
+
+    // Every logical operator is reported, the program is still parsed
+    assert_eq!(errs.len(), 6, "{errs:?}");
+    assert!(errs.iter().all(|err| matches!(
+        err.reason(),
+        SimpleReason::Custom(msg) if msg.ends_with("is a part of the BOOLTHEN extension, which is disabled")
+    )));
+    assert_eq!(statements(program.unwrap()).len(), 2);
+}
+
+#[test]
+fn precedence() {
+    let file_name = "parser/valid/precedence.txt";
+    let file = common::load_test_code(file_name);
+
+    let (_, (program, errs), strings) = parse_strings(file_name, &file, Extensions::default());
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = statements(program.unwrap());
+    let rpn = program
+        .iter()
+        .map(|stmt| match stmt {
+            Stmt::VarAssignment { expression, .. } => expression.to_reverse_polish(),
+            stmt => panic!("Expected an assignment, found {stmt:?}"),
+        })
+        .collect::<Vec<_>>();
+
+    let var = |name: &str| Expression::Term(Term::Var(name.into()));
+
+    // `.` binds looser than `+` like in PHP 8
+    assert_eq!(
+        rpn[0],
+        [
+            string(&strings, "n: "),
+            var("b"),
+            Expression::Term(Term::Int(1)),
+            var("c"),
+            Expression::Negate,
+            Expression::Multiply,
+            Expression::Add,
+            Expression::Concat,
+        ]
+    );
+
+    // Non associative operators of different precedence can be mixed
+    assert_eq!(
+        rpn[1],
+        [
+            var("a"),
+            var("b"),
+            Expression::Smaller,
+            var("c"),
+            var("d"),
+            Expression::GreaterEquals,
+            Expression::Equals,
+        ]
+    );
+}
+
+#[test]
+fn chained_comparison() {
+    let file_name = "parser/invalid/chained_comparison.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    let file = map.files().next().unwrap();
+
+    // The second operator of a chain is reported, a parenthesized chain is fine
+    let reported = errs
+        .iter()
+        .map(|err| match err.reason() {
+            SimpleReason::Custom(msg) => (file.slice(&err.span()), msg.as_str()),
+            reason => panic!("Expected a custom error, found {reason:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reported,
+        [
+            ("<=", "Error:`<=` can't be chained with `<` without parentheses, the operators are non associative"),
+            ("!==", "Error:`!==` can't be chained with `===` without parentheses, the operators are non associative"),
+        ]
+    );
+
+    // The chains are still parsed left to right
+    assert_eq!(statements(program.unwrap()).len(), 3);
+}
+
+#[test]
+fn nested_calls() {
+    let file_name = "parser/valid/calls.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs), strings) = parse_strings(file_name, &file, Extensions::default());
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = statements(program.unwrap());
+    let file = map.files().next().unwrap();
+
+    let var = |name: &str| Expression::Term(Term::Var(name.into()));
+    let int = |i| Expression::Term(Term::Int(i));
+    let call = |ident: &str, args| Expression::Call {
+        ident: ident.into(),
+        args,
+    };
+
+    let Stmt::VarAssignment { expression, .. } = &program[0] else {
+        panic!("Expected an assignment, found {:?}", program[0]);
+    };
+    assert_eq!(
+        expression.to_reverse_polish(),
+        [var("s"), call("strlen", 1), int(1), Expression::Add]
+    );
+
+    // A call used as an argument keeps its own span
+    let Stmt::Exp { expr, .. } = &program[1] else {
+        panic!("Expected an expression statement, found {:?}", program[1]);
+    };
+    let ExprKind::Call { args, .. } = &expr.kind else {
+        panic!("Expected a call, found {expr:?}");
+    };
+    assert_eq!(file.slice(&args[0].span), "g($a)");
+    assert_eq!(
+        expr.to_reverse_polish(),
+        [var("a"), call("g", 1), int(2), call("f", 2)]
+    );
+
+    let Stmt::VarAssignment { expression, .. } = &program[2] else {
+        panic!("Expected an assignment, found {:?}", program[2]);
+    };
+    assert_eq!(
+        expression.to_reverse_polish(),
+        [
+            call("f", 0),
+            Expression::Negate,
+            int(1),
+            call("h", 0),
+            call("g", 2),
+            string(&strings, "a"),
+            Expression::Concat,
+            Expression::Multiply,
+        ]
+    );
+}
+
+#[test]
+fn calls_without_funexp() {
+    let file_name = "parser/valid/calls.txt";
+    let file = common::load_test_code(file_name);
+
+    let extensions = Extensions::default().without(Extension::FunExp);
+    let (map, (_, errs)) = parse_with(file_name, &file, extensions);
+    let file = map.files().next().unwrap();
+
+    // Only the calls inside other expressions are reported
+    let calls = errs
+        .iter()
+        .map(|err| file.slice(&err.span()))
+        .collect::<Vec<_>>();
+    assert_eq!(calls, ["strlen($s)", "g($a)", "f()", "g(1, h())", "h()"]);
+}
+
+/// The function definitions of a program
+fn functions(program: Vec<Item>) -> Vec<FuncDef> {
+    program
+        .into_iter()
+        .map(|item| match item {
+            Item::FuncDef(func) => func,
+            Item::Stmt(stmt) => panic!("Unexpected statement {stmt:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn function_definitions() {
+    let file_name = "parser/valid/functions.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let functions = functions(program.unwrap());
+    let file = map.files().next().unwrap();
+
+    let name = &functions[0];
+    assert_eq!(name.func_ident.as_str(), "name");
+    assert_eq!(
+        name.args
+            .iter()
+            .map(|arg| (file.slice(&arg.span), arg.ident.as_str(), arg.r#type))
+            .collect::<Vec<_>>(),
+        [
+            ("?int $a", "a", Type::Int { nullable: true }),
+            ("string $b", "b", Type::String { nullable: false }),
+        ]
+    );
+    assert_eq!(*name.return_ty, Type::Float { nullable: true });
+    assert_eq!(file.slice(&name.return_ty.0), "?float");
+    assert_eq!(name.body.len(), 1);
+
+    let noop = &functions[1];
+    assert!(noop.args.is_empty());
+    assert_eq!(*noop.return_ty, Type::Void);
+    assert_eq!(functions.len(), 2);
+}
+
+#[test]
+fn duplicate_parameter() {
+    let file_name = "parser/invalid/duplicate_parameter.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    let file = map.files().next().unwrap();
+
+    assert_eq!(errs.len(), 1, "{errs:?}");
+    assert_eq!(file.slice(&errs[0].span()), "?string $a");
+    assert!(matches!(
+        errs[0].reason(),
+        SimpleReason::Custom(msg) if msg == "Error:parameter `$a` is already declared"
+    ));
+
+    // Both parameters are kept
+    assert_eq!(functions(program.unwrap())[0].args.len(), 3);
+}
+
+#[test]
+fn missing_return_type() {
+    let file_name = "parser/invalid/missing_return_type.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    let file = map.files().next().unwrap();
+
+    // Reported right after the parameters
+    assert_eq!(errs.len(), 1, "{errs:?}");
+    assert_eq!(file.line_col(errs[0].span().start), (2, 18));
+    assert!(matches!(
+        errs[0].reason(),
+        SimpleReason::Custom(msg) if msg == "Error:function `f` is missing its return type, like `: void`"
+    ));
+
+    // The function is treated as void
+    let functions = functions(program.expect("Parser failed to recover"));
+    assert_eq!(*functions[0].return_ty, Type::Void);
+}
+
+#[test]
+fn elseif_chains() {
+    let file_name = "parser/valid/elseif.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = statements(program.unwrap());
+    let file = map.files().next().unwrap();
+
+    // `elseif` and `else if` are both an `if` nested inside the `else` branch
+    let Stmt::If(first) = &program[0] else {
+        panic!("Expected an if statement, found {:?}", program[0]);
+    };
+    let [Stmt::If(second)] = &first.else_body[..] else {
+        panic!("Expected an elseif, found {:?}", first.else_body);
+    };
+    assert!(file.slice(&second.span).starts_with("elseif ($b)"));
+    let [Stmt::If(third)] = &second.else_body[..] else {
+        panic!("Expected an else if, found {:?}", second.else_body);
+    };
+    assert!(file.slice(&third.span).starts_with("else if ($c)"));
+    assert!(matches!(third.else_body[..], [Stmt::Exp { .. }]));
+
+    // The whole chain belongs to the first `if`
+    assert_eq!(first.span.end, third.span.end);
+
+    let Stmt::If(If { else_body, .. }) = &program[1] else {
+        panic!("Expected an if statement, found {:?}", program[1]);
+    };
+    assert!(else_body.is_empty());
+    assert_eq!(program.len(), 2);
+}
+
+#[test]
+fn strict_if() {
+    let file_name = "parser/valid/elseif.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse_with(file_name, &file, Extensions::NONE);
+    let file = map.files().next().unwrap();
+
+    let reported = errs
+        .iter()
+        .map(|err| match err.reason() {
+            SimpleReason::Custom(msg) => (file.slice(&err.span()), msg.as_str()),
+            reason => panic!("Expected a custom error, found {reason:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reported,
+        [
+            (
+                "elseif",
+                "Error:`elseif` is a part of the BOOLTHEN extension, which is disabled"
+            ),
+            (
+                "else if",
+                "Error:`else if` is a part of the BOOLTHEN extension, which is disabled"
+            ),
+            (
+                "",
+                "Error:an `if` without `else` is a part of the BOOLTHEN extension, which is disabled"
+            ),
+        ]
+    );
+    assert_eq!(file.line_col(errs[2].span().start), (11, 23));
+
+    // The program is still parsed
+    assert_eq!(statements(program.unwrap()).len(), 2);
+}
+
+#[test]
+fn loops() {
+    let file_name = "parser/valid/loops.txt";
+    let file = common::load_test_code(file_name);
+
+    let (map, (program, errs)) = parse(file_name, &file);
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = statements(program.unwrap());
+    let file = map.files().next().unwrap();
+
+    let Stmt::For(For {
+        init,
+        cond,
+        step,
+        body,
+        ..
+    }) = &program[0]
+    else {
+        panic!("Expected a for loop, found {:?}", program[0]);
+    };
+
+    let sources = |stmts: &[Stmt]| {
+        stmts
+            .iter()
+            .map(|stmt| file.slice(stmt.span()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sources(init), ["$i = 0", "$j = 10"]);
+    assert_eq!(file.slice(&cond.as_ref().unwrap().span), "$i < $j");
+    assert_eq!(sources(step), ["$i = $i + 1", "$j = $j - 1"]);
+
+    assert!(
+        matches!(&body[0], Stmt::If(If { if_body, .. }) if matches!(if_body[..], [Stmt::Continue { depth: 1, .. }]))
+    );
+    assert!(
+        matches!(&body[1], Stmt::While(While { body, .. }) if matches!(body[..], [Stmt::Break { depth: 2, .. }]))
+    );
+
+    // Every part of the header is optional
+    assert!(matches!(
+        &program[1],
+        Stmt::For(For { init, cond: None, step, body, .. })
+            if init.is_empty() && step.is_empty() && matches!(body[..], [Stmt::Break { depth: 1, .. }])
+    ));
+    assert_eq!(program.len(), 2);
+}
+
+#[test]
+fn loops_without_cycles() {
+    let file_name = "parser/valid/loops.txt";
+    let file = common::load_test_code(file_name);
+
+    let extensions = Extensions::default().without(Extension::Cycles);
+    let (map, (_, errs)) = parse_with(file_name, &file, extensions);
+    let file = map.files().next().unwrap();
+
+    let keywords = errs
+        .iter()
+        .map(|err| file.slice(&err.span()))
+        .collect::<Vec<_>>();
+    assert_eq!(keywords, ["for", "continue", "break", "for", "break"]);
+}
+
+#[test]
+fn break_zero() {
+    let file_name = "parser/invalid/break_zero.txt";
+    let file = common::load_test_code(file_name);
+
+    let (_, (program, errs)) = parse(file_name, &file);
+
+    assert_eq!(errs.len(), 1, "{errs:?}");
+    assert!(matches!(
+        errs[0].reason(),
+        SimpleReason::Custom(msg) if msg == "Error:`break 0` isn't allowed, the depth has to be at least 1"
+    ));
+
+    // Treated as `break 1`
+    let program = statements(program.unwrap());
+    assert!(
+        matches!(&program[0], Stmt::While(While { body, .. }) if matches!(body[..], [Stmt::Break { depth: 1, .. }]))
+    );
+}
+
+#[test]
+fn invalid_programs() {
+    let files = common::load_test_group("parser/invalid");
+
+    for (file, src) in files {
+        let (_, (_, errs)) = parse(&file, &src);
+
+        assert!(
+            !errs.is_empty(),
+            "Parser failed to spot the mistake in file {file}"
+        );
+    }
+}
+
+#[test]
+fn error_recovery() {
+    let file_name = "parser/invalid/recovery.txt";
+    let file = common::load_test_code(file_name);
+
+    let (_, (program, errs)) = parse(file_name, &file);
+
+    // Both broken assignments are reported and the one in between still parses
+    assert_eq!(errs.len(), 2, "{errs:?}");
+
+    let program = statements(program.expect("Parser failed to recover"));
+
+    assert_eq!(program.len(), 3);
+    assert!(matches!(program[0], Stmt::Error { .. }));
+    assert!(matches!(&program[1], Stmt::VarAssignment { ident, .. } if ident.as_str() == "b"));
+    assert!(matches!(program[2], Stmt::Error { .. }));
+}
+
+#[test]
+fn blocks() {
+    let file_name = "parser/valid/blocks.txt";
+    let file = common::load_test_code(file_name);
+
+    let (_, (program, errs)) = parse(file_name, &file);
+    assert!(errs.is_empty(), "{errs:?}");
+
+    let program = program.unwrap();
+    let Item::FuncDef(FuncDef { body, .. }) = &program[0] else {
+        panic!("Expected a function definition, found {:?}", program[0]);
+    };
+
+    assert!(matches!(&body[0], Stmt::Block { body, .. } if body.len() == 1));
+    assert!(matches!(body[2], Stmt::Return { expr: Some(_), .. }));
+    assert_eq!(body.len(), 3, "The empty statement should be skipped");
+
+    let Stmt::While(While {
+        body: loop_body, ..
+    }) = &body[1]
+    else {
+        panic!("Expected a while loop, found {:?}", body[1]);
+    };
+    assert!(matches!(loop_body[0], Stmt::VarAssignment { .. }));
+    assert!(matches!(&loop_body[1], Stmt::If(If { else_body, .. }) if else_body.len() == 1));
+
+    // A bare block inside an if at the top level
+    assert!(
+        matches!(&program[1], Item::Stmt(Stmt::If(If { if_body, .. })) if matches!(if_body[..], [Stmt::Block { .. }]))
+    );
+    assert_eq!(program.len(), 2);
+}
+
+#[test]
+fn body_recovery() {
+    let file_name = "parser/invalid/body_recovery.txt";
+    let file = common::load_test_code(file_name);
+
+    let (_, (program, errs)) = parse(file_name, &file);
+
+    // The broken assignment and the unterminated return
+    assert_eq!(errs.len(), 2, "{errs:?}");
+
+    // The statement after the if still parses
+    let program = program.expect("Parser failed to recover");
+    assert!(matches!(
+        program.last(),
+        Some(Item::Stmt(Stmt::Exp { expr: Expr { kind: ExprKind::Call { ident, .. }, .. }, .. })) if ident.as_str() == "write"
+    ));
+}
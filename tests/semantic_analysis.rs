@@ -0,0 +1,203 @@
+mod common;
+
+use chumsky::error::SimpleReason;
+use chumsky::prelude::Parser;
+use chumsky::Stream;
+use rust_ifj22_compiler::semantic_analysis::{
+    check_loop_control, resolve_functions, FunctionTable,
+};
+use rust_ifj22_compiler::source_map::{FileId, SourceMap};
+use rust_ifj22_compiler::syntactic_analysis::ast::{Expression, For, If, Item, Stmt, While};
+use rust_ifj22_compiler::syntactic_analysis::{parser, Extensions};
+use rust_ifj22_compiler::{lexer, Span, Symbol, Token};
+
+fn parse(file_name: &str) -> (SourceMap, FileId, Vec<Item>) {
+    let file = common::load_test_code(file_name);
+    let (map, id) = common::source_map(file_name, &file);
+    let (tokens, _) = lexer(&map[id]).parse(map[id].stream()).unwrap();
+
+    let stream = Stream::from_iter(map[id].eoi(), tokens.into_iter());
+    let program = parser(Extensions::default()).parse(stream).unwrap();
+
+    (map, id, program)
+}
+
+fn messages(errs: &[chumsky::error::Simple<Token, Span>]) -> Vec<&str> {
+    errs.iter()
+        .map(|err| match err.reason() {
+            SimpleReason::Custom(msg) => msg.as_str(),
+            reason => panic!("Expected a custom error, found {reason:?}"),
+        })
+        .collect()
+}
+
+/// The source of every error paired with its message
+fn reported<'a>(
+    map: &'a SourceMap,
+    id: FileId,
+    errs: &'a [chumsky::error::Simple<Token, Span>],
+) -> Vec<(&'a str, &'a str)> {
+    errs.iter()
+        .map(|err| map[id].slice(&err.span()))
+        .zip(messages(errs))
+        .collect()
+}
+
+/// Names of the called functions in the order they're called
+fn calls(body: &[Stmt]) -> Vec<Symbol> {
+    let mut found = Vec::new();
+
+    for stmt in body {
+        let (exprs, bodies) = match stmt {
+            Stmt::If(If {
+                expr,
+                if_body,
+                else_body,
+                ..
+            }) => (vec![expr], vec![if_body, else_body]),
+            Stmt::While(While { expr, body, .. }) => (vec![expr], vec![body]),
+            Stmt::For(For {
+                init,
+                cond,
+                step,
+                body,
+                ..
+            }) => (cond.iter().collect(), vec![init, step, body]),
+            Stmt::Block { body, .. } => (vec![], vec![body]),
+            Stmt::Exp { expr, .. }
+            | Stmt::VarAssignment {
+                expression: expr, ..
+            }
+            | Stmt::Return {
+                expr: Some(expr), ..
+            } => (vec![expr], vec![]),
+            _ => (vec![], vec![]),
+        };
+
+        for expr in exprs {
+            found.extend(
+                expr.to_reverse_polish()
+                    .into_iter()
+                    .filter_map(|expression| match expression {
+                        Expression::Call { ident, .. } => Some(ident),
+                        _ => None,
+                    }),
+            );
+        }
+        for body in bodies {
+            found.extend(calls(body));
+        }
+    }
+
+    found
+}
+
+fn program_calls(program: &[Item]) -> Vec<Symbol> {
+    program
+        .iter()
+        .flat_map(|item| match item {
+            Item::FuncDef(func) => calls(&func.body),
+            Item::Stmt(stmt) => calls(std::slice::from_ref(stmt)),
+        })
+        .collect()
+}
+
+#[test]
+fn case_insensitive_lookup() {
+    let (_, _, mut program) = parse("identifiers/casing.txt");
+
+    let mut errs = Vec::new();
+    resolve_functions(&mut program, FunctionTable::new(), &mut |err| {
+        errs.push(err)
+    });
+
+    assert!(errs.is_empty(), "Unexpected errors {errs:?}");
+    assert_eq!(
+        program_calls(&program),
+        ["write".into(), "Greet".into(), "strlen".into()],
+        "Calls weren't resolved to the declared names"
+    );
+}
+
+#[test]
+fn casing_warnings() {
+    let (map, id, mut program) = parse("identifiers/casing.txt");
+
+    let mut errs = Vec::new();
+    let table = FunctionTable::new().warn_casing(true);
+    resolve_functions(&mut program, table, &mut |err| errs.push(err));
+
+    assert_eq!(
+        reported(&map, id, &errs),
+        [
+            (
+                "Write(\"hi\", $name)",
+                "Warn:`Write` differs in casing from the declared `write`"
+            ),
+            (
+                "greet(null)",
+                "Warn:`greet` differs in casing from the declared `Greet`"
+            ),
+            (
+                "STRLEN(\"abc\")",
+                "Warn:`STRLEN` differs in casing from the declared `strlen`"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn function_declarations() {
+    let (map, id, mut program) = parse("semantic/functions.txt");
+
+    let mut errs = Vec::new();
+    resolve_functions(&mut program, FunctionTable::new(), &mut |err| {
+        errs.push(err)
+    });
+
+    assert_eq!(
+        reported(&map, id, &errs),
+        [
+            ("LATE", "Error:function `LATE` is already declared"),
+            ("strlen", "Error:function `strlen` is a built-in function"),
+            (
+                "undefined(late(2))",
+                "Error:call to undefined function `undefined`"
+            ),
+        ]
+    );
+    // `late` is called above its definition, arguments are called before the function
+    assert_eq!(
+        program_calls(&program),
+        [
+            "late".into(),
+            "late".into(),
+            "undefined".into(),
+            "write".into()
+        ]
+    );
+}
+
+#[test]
+fn loop_control() {
+    let (map, id, program) = parse("semantic/loop_control.txt");
+
+    let mut errs = Vec::new();
+    check_loop_control(&program, &mut |err| errs.push(err));
+
+    assert_eq!(
+        reported(&map, id, &errs),
+        [
+            ("break;", "Error:`break` outside of a loop"),
+            (
+                "continue 3;",
+                "Error:`continue 3` can't exit 3 loops, it's nested in only 2"
+            ),
+            (
+                "break 2;",
+                "Error:`break 2` can't exit 2 loops, it's nested in only 1"
+            ),
+            ("continue;", "Error:`continue` outside of a loop"),
+        ]
+    );
+}
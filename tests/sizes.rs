@@ -1,12 +1,17 @@
 use std::mem::size_of;
 
 use chumsky::error::Simple;
-use rust_ifj22_compiler::syntactic_analysis::ast::MainBody;
-use rust_ifj22_compiler::Token;
+use rust_ifj22_compiler::syntactic_analysis::ast::Stmt;
+use rust_ifj22_compiler::{Symbol, Token};
 
-const MAX_TOKEN_SIZE: usize = 32;
+const MAX_TOKEN_SIZE: usize = 24;
 const MAX_AST_SIZE: usize = 64;
 
+#[test]
+fn test_symbol_size() {
+    assert_eq!(size_of::<Symbol>(), 4);
+}
+
 #[test]
 fn test_token_size() {
     println!("Token size: {}", size_of::<Token>());
@@ -15,8 +20,8 @@ fn test_token_size() {
 }
 
 #[test]
-#[ignore] // This may get enabled when the code gets optimized (at the time of writing MainBody is 152 bytes)
+#[ignore] // This may get enabled when the code gets optimized (at the time of writing Stmt is 128 bytes)
 fn test_ast_token_size() {
-    println!("Token size: {}", size_of::<MainBody>());
-    assert!(size_of::<MainBody>() <= MAX_TOKEN_SIZE);
+    println!("Token size: {}", size_of::<Stmt>());
+    assert!(size_of::<Stmt>() <= MAX_AST_SIZE);
 }
@@ -0,0 +1,68 @@
+use rust_ifj22_compiler::source_map::SourceMap;
+use rust_ifj22_compiler::Span;
+
+#[test]
+fn line_col() {
+    let mut map = SourceMap::new();
+    let id = map.add("lines.php", "<?php\n$a = \"ž\";\n\n$b");
+    let file = &map[id];
+
+    assert_eq!(file.line_col(0), (0, 0));
+    assert_eq!(file.line_col(5), (0, 5));
+    assert_eq!(file.line_col(6), (1, 0));
+    // `ž` takes two bytes but only a single column
+    assert_eq!(file.line_col(14), (1, 7));
+    assert_eq!(file.line_col(17), (2, 0));
+    assert_eq!(file.line_col(18), (3, 0));
+    assert_eq!(file.line_col(20), (3, 2));
+}
+
+#[test]
+fn char_byte_conversion() {
+    let mut map = SourceMap::new();
+    let id = map.add("chars.php", "ab\nčď\n😀e");
+    let file = &map[id];
+
+    for (char, (byte, _)) in file.src().char_indices().enumerate() {
+        assert_eq!(file.byte_to_char(byte), char);
+        assert_eq!(file.char_to_byte(char), byte);
+    }
+
+    let len = file.src().len();
+    assert_eq!(file.byte_to_char(len), file.src().chars().count());
+    assert_eq!(file.char_to_byte(file.src().chars().count()), len);
+}
+
+#[test]
+fn multiple_files() {
+    let mut map = SourceMap::new();
+    let first = map.add("first.php", "<?php\n");
+    let second = map.add("second.php", "declare(strict_types=1);\n");
+
+    assert_ne!(first, second);
+    assert_eq!(map.files().count(), 2);
+    assert_eq!(map[first].name(), "first.php");
+    assert_eq!(map[second].name(), "second.php");
+
+    // Spans remember which file they point into
+    let span = map[second].span(0..7);
+    assert_eq!(span, Span::new(second, 0..7));
+    assert_eq!(map[span.file].slice(&span), "declare");
+}
+
+#[test]
+fn stream_spans_bytes() {
+    use chumsky::prelude::*;
+
+    let mut map = SourceMap::new();
+    let id = map.add("stream.php", "ž!");
+
+    let spans = any::<char, Simple<char, Span>>()
+        .map_with_span(|_, span| span)
+        .repeated()
+        .then_ignore(end())
+        .parse(map[id].stream())
+        .unwrap();
+
+    assert_eq!(spans, [map[id].span(0..2), map[id].span(2..3)]);
+}
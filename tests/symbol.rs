@@ -0,0 +1,17 @@
+use rust_ifj22_compiler::Symbol;
+
+#[test]
+fn interning() {
+    // Enough names to fill a few buckets of the string table
+    let names = (0..1000).map(|i| format!("name_{i}")).collect::<Vec<_>>();
+    let symbols = names
+        .iter()
+        .map(|name| Symbol::intern(name))
+        .collect::<Vec<_>>();
+
+    for (name, symbol) in names.iter().zip(&symbols) {
+        assert_eq!(symbol.as_str(), name);
+        assert_eq!(Symbol::intern(name), *symbol, "{name} was interned twice");
+        assert_eq!(Symbol::from(name.clone()), *symbol);
+    }
+}